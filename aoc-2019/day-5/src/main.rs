@@ -1,20 +1,22 @@
 extern crate intcode;
 
+use intcode::io::FnIo;
+
 fn main() {
     let program = intcode::load_program_from_file("program.txt").expect("Could not read program");
-    let output = |x| -> () { println!("Output: {}", x)};
+    let output = |x| println!("Output: {}", x);
     
     println!("Part one solution");
     let input = || {
         1
     };
     let mut p1 = program.clone();
-    intcode::run_interpreter(&mut p1, input, output);
+    intcode::run_interpreter(&mut p1, &mut FnIo::new(input, output)).expect("Program faulted");
 
     println!("Part two solution");    
     let input = || {        
         5
     };    
     let mut p = program.clone();
-    intcode::run_interpreter(&mut p, input, output);
+    intcode::run_interpreter(&mut p, &mut FnIo::new(input, output)).expect("Program faulted");
 }
@@ -1,3 +1,16 @@
+use intcode::amplifiers::{AmplifierChain, Execution};
+
 fn main() {
-    println!("Hello, world!");
+    let program = intcode::load_program_from_file("program.txt").expect("Could not read program");
+    let chain = AmplifierChain::new(program);
+
+    // Part 1 Solution:
+    let (signal, phases) = chain.find_max_signal(&[0, 1, 2, 3, 4], false, Execution::Serial)
+                                .expect("No phase setting produced a thruster signal");
+    println!("Part one: max thruster signal: {} with phases {:?}", signal, phases);
+
+    // Part 2 Solution:
+    let (signal, phases) = chain.find_max_signal(&[5, 6, 7, 8, 9], true, Execution::Threaded)
+                                .expect("No phase setting produced a thruster signal");
+    println!("Part two: max feedback thruster signal: {} with phases {:?}", signal, phases);
 }
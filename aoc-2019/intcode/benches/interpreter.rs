@@ -0,0 +1,171 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use intcode::instructions::{self, Instruction, op_code::{OpCode, OpCodeId}};
+use intcode::io::QueueIo;
+use intcode::lexer::{self, InstructionType, Parameter};
+use intcode::machine::{Backend, Intcode, RunState};
+use intcode::memory::Memory;
+use intcode::parameters::ParameterMode;
+use num_traits::FromPrimitive;
+use std::hint::black_box;
+
+/// Amplifier controller software from the first day 7 example
+const AMPLIFIER: [i64; 17] = [3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0];
+
+/// Counts a memory cell down to zero
+const COUNTDOWN: &str = "
+start:   add [counter], #-1, [counter]
+         jt [counter], #start
+         hlt
+counter: data 10000
+";
+
+/// The string based decoder that the interpreter used before decoding arithmetically
+fn legacy_parse_instruction_type(instruction: i64) -> Option<InstructionType> {
+    let mut chars: Vec<char> = instruction.to_string().chars().collect();
+    while chars.len() < 5 {
+        chars.insert(0, '0');
+    }
+    let mode = |c: char| match c {
+        '0' => Some(ParameterMode::Position),
+        '1' => Some(ParameterMode::Immediate),
+        '2' => Some(ParameterMode::Relative),
+        _   => None
+    };
+    let a = mode(chars[0])?;
+    let b = mode(chars[1])?;
+    let c = mode(chars[2])?;
+    let op_code = chars.iter().skip(3).collect::<String>().parse::<i64>().ok()?;
+    Some(InstructionType{ op_code,
+                          a: Parameter{mode: a, value: 0},
+                          b: Parameter{mode: b, value: 0},
+                          c: Parameter{mode: c, value: 0}})
+}
+
+/// The interpreter loop as it was before instructions were cached, re-lexing every word
+/// with the string decoder and boxing a trait object for every executed instruction
+fn legacy_run(program: &[i64], inputs: &[i64]) -> Vec<i64> {
+    let mut memory = Memory::new(program.to_vec());
+    let mut io = QueueIo::new(inputs.iter().copied());
+    let mut relative_base = 0;
+    let mut ip = 0;
+    loop {
+        let words = memory.read_range(ip as usize, 4);
+        let instruction = legacy_parse_instruction_type(words[0]).expect("invalid instruction");
+        if let Some(OpCodeId::Complete) = FromPrimitive::from_i64(instruction.op_code) {
+            return io.drain_outputs();
+        }
+        let op = instructions::parse_from_slice(instruction, &words).expect("unknown op code");
+        ip = op.apply(&mut memory, ip, &mut relative_base, &mut io).expect("program faulted");
+    }
+}
+
+/// Runs a program on the current interpreter
+fn run(program: &[i64], inputs: &[i64]) -> Vec<i64> {
+    run_on(Backend::Interpreter, program, inputs)
+}
+
+/// Runs a program on the threaded backend
+fn run_threaded(program: &[i64], inputs: &[i64]) -> Vec<i64> {
+    run_on(Backend::Threaded, program, inputs)
+}
+
+/// Helper function to run a program on a backend
+fn run_on(backend: Backend, program: &[i64], inputs: &[i64]) -> Vec<i64> {
+    let mut machine = Intcode::new(program.to_vec()).with_backend(backend);
+    inputs.iter().for_each(|x| machine.push_input(*x));
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    machine.drain_outputs()
+}
+
+/// Finds the strongest thruster signal over every phase permutation of a serial amplifier chain
+fn amplifier_search(interpreter: fn(&[i64], &[i64]) -> Vec<i64>) -> i64 {
+    let mut best = i64::MIN;
+    let mut phases = [0, 1, 2, 3, 4];
+    permute(&mut phases, 0, &mut |phases| {
+        let signal = phases.iter().fold(0, |signal, phase| interpreter(&AMPLIFIER, &[*phase, signal])[0]);
+        best = best.max(signal);
+    });
+    best
+}
+
+/// Helper function to visit every permutation of a slice
+fn permute(values: &mut [i64], start: usize, visit: &mut impl FnMut(&[i64])) {
+    if start == values.len() {
+        visit(values);
+        return;
+    }
+    for i in start..values.len() {
+        values.swap(start, i);
+        permute(values, start + 1, visit);
+        values.swap(start, i);
+    }
+}
+
+fn decode(c: &mut Criterion) {
+    let words = [1002, 21107, 99, 3, 204, 1105];
+    let mut group = c.benchmark_group("decode");
+    group.bench_function("string", |b| b.iter(|| {
+        words.iter().map(|w| legacy_parse_instruction_type(black_box(*w)).unwrap().op_code).sum::<i64>()
+    }));
+    group.bench_function("arithmetic", |b| b.iter(|| {
+        words.iter().map(|w| lexer::parse_instruction_type(black_box(*w)).unwrap().op_code).sum::<i64>()
+    }));
+    group.finish();
+}
+
+fn dispatch(c: &mut Criterion) {
+    let words = [21107, 1, 2, 5];
+    let mut group = c.benchmark_group("dispatch");
+    group.bench_function("boxed", |b| b.iter(|| {
+        let instruction = lexer::parse_instruction_type(black_box(words[0])).unwrap();
+        instructions::parse_from_slice(instruction, black_box(&words)).unwrap().get_instruction_pointer_offset()
+    }));
+    group.bench_function("enum", |b| b.iter(|| {
+        Instruction::parse_from_slice(black_box(&words)).unwrap().get_instruction_pointer_offset()
+    }));
+    group.finish();
+}
+
+fn interpreter(c: &mut Criterion) {
+    let countdown = intcode::assembler::assemble(COUNTDOWN).unwrap();
+    assert_eq!(legacy_run(&AMPLIFIER, &[4, 0]), run(&AMPLIFIER, &[4, 0]));
+    assert_eq!(amplifier_search(legacy_run), amplifier_search(run));
+    assert_eq!(amplifier_search(run), amplifier_search(run_threaded));
+
+    let mut group = c.benchmark_group("countdown");
+    group.bench_function("legacy", |b| b.iter(|| legacy_run(black_box(&countdown), &[])));
+    group.bench_function("cached", |b| b.iter(|| run(black_box(&countdown), &[])));
+    group.bench_function("threaded", |b| b.iter(|| run_threaded(black_box(&countdown), &[])));
+    group.finish();
+
+    let mut group = c.benchmark_group("amplifier_search");
+    group.bench_function("legacy", |b| b.iter(|| amplifier_search(legacy_run)));
+    group.bench_function("cached", |b| b.iter(|| amplifier_search(run)));
+    group.bench_function("threaded", |b| b.iter(|| amplifier_search(run_threaded)));
+    group.finish();
+}
+
+/// Helper function to run a program that outputs `count` distinct constants up to its final input
+fn warmed_up(backend: Backend, count: i64) -> Intcode {
+    let mut program = (0..count).flat_map(|x| [104, x]).collect::<Vec<i64>>();
+    program.extend_from_slice(&[3, 0, 99]);
+    let mut machine = Intcode::new(program).with_backend(backend);
+    assert_eq!(machine.run_until_blocked(), RunState::AwaitingInput);
+    machine.drain_outputs();
+    machine
+}
+
+fn fork(c: &mut Criterion) {
+    // Forking copies neither memory nor decoded code, so the larger program should fork as fast
+    let mut group = c.benchmark_group("fork");
+    for (name, backend) in [("cached", Backend::Interpreter), ("threaded", Backend::Threaded)] {
+        for count in [10, 10000] {
+            let machine = warmed_up(backend, count);
+            group.bench_function(format!("{}/{}", name, count), |b| b.iter(|| black_box(&machine).fork()));
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode, dispatch, interpreter, fork);
+criterion_main!(benches);
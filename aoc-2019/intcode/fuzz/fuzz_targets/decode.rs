@@ -0,0 +1,24 @@
+//! Decodes, disassembles and analyses arbitrary words, none of which may panic, and checks
+//! that every listing reassembles to the same words
+
+#![no_main]
+
+use intcode::analysis::ControlFlowGraph;
+use intcode::instructions::{Instruction, op_code::OpCode};
+use intcode::registry::InstructionSet;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Pairs of bytes make small words, so that op codes and modes come up often
+    let words = data.chunks(2)
+        .map(|pair| i16::from_le_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as i64)
+        .collect::<Vec<i64>>();
+    let standard = InstructionSet::standard();
+    for start in 0..words.len() {
+        let decoded = Instruction::parse_from_slice(&words[start..]);
+        assert_eq!(decoded, standard.decode(&words[start..]));
+    }
+    let listing = intcode::disassembler::listing(&words);
+    assert_eq!(intcode::assembler::assemble(&listing).as_ref(), Ok(&words));
+    ControlFlowGraph::build(&words).to_dot();
+});
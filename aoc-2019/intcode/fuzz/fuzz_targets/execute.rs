@@ -0,0 +1,50 @@
+//! Runs arbitrary programs for a bounded number of steps on every backend
+//!
+//! The first byte picks the memory, paged when its lowest bit is set, and whether the rest of
+//! the input holds 8 byte words rather than pairs of bytes. Both backends must agree
+
+#![no_main]
+
+use intcode::io::QueueIo;
+use intcode::machine::{Backend, Intcode};
+use intcode::memory::Memory;
+use libfuzzer_sys::fuzz_target;
+
+/// Number of instructions after which a run is stopped
+const STEP_LIMIT: u64 = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let (options, bytes) = match data.split_first() {
+        Some(split) => split,
+        None => return
+    };
+    let width = match options & 2 {
+        0 => 2,
+        _ => 8
+    };
+    let program = bytes.chunks(width)
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            match width {
+                2 => i16::from_le_bytes([word[0], word[1]]) as i64,
+                _ => i64::from_le_bytes(word)
+            }
+        })
+        .collect::<Vec<i64>>();
+    let inputs = program.iter().rev().take(4).copied().collect::<Vec<i64>>();
+
+    let results = [Backend::Interpreter, Backend::Threaded].map(|backend| {
+        let memory = match options & 1 {
+            0 => Memory::new(program.clone()),
+            _ => Memory::paged(&program)
+        };
+        let mut machine = Intcode::with_memory(memory).with_backend(backend);
+        let outcome = machine.run(&mut QueueIo::new(inputs.clone()), Some(STEP_LIMIT));
+        (outcome, machine.relative_base(), machine.into_memory())
+    });
+    let [interpreted, threaded] = results;
+    assert_eq!(interpreted.0, threaded.0);
+    assert_eq!(interpreted.1, threaded.1);
+    assert!(interpreted.2.diff(&threaded.2).is_empty());
+});
@@ -0,0 +1,15 @@
+//! Loads arbitrary bytes as a program in either format and checks that loading never panics
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(program) = intcode::loader::read_program(data) {
+        let encoded = intcode::loader::encode_binary(&program);
+        assert_eq!(intcode::loader::decode_binary(&encoded), Ok(program));
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = intcode::assembler::assemble(text);
+    }
+});
@@ -0,0 +1,218 @@
+use crate::io::ChannelIo;
+use crate::machine::{Intcode, RunState};
+use std::sync::mpsc;
+use std::thread;
+
+/// How the amplifiers in a chain are executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Execution {
+    /// All amplifiers are stepped in turn on the calling thread
+    Serial,
+    /// Every amplifier runs on its own thread connected to its neighbours by channels
+    Threaded,
+}
+
+/// A series of amplifiers that all run copies of the same intcode program with the
+/// output of each amplifier wired into the input of the next
+#[derive(Debug, Clone)]
+pub struct AmplifierChain {
+    program: Vec<i64>
+}
+
+impl AmplifierChain {
+    /// Creates a new chain from the amplifier controller software
+    ///
+    /// # Arguments
+    /// * `program` - The intcode program that every amplifier runs
+    pub fn new(program: Vec<i64>) -> AmplifierChain {
+        AmplifierChain { program }
+    }
+
+    /// Runs one amplifier per phase setting and returns the last signal sent to the thrusters
+    ///
+    /// # Arguments
+    /// * `phases`    - Phase setting for each amplifier, in chain order
+    /// * `feedback`  - Wires the last amplifier back into the first until every amplifier halts
+    /// * `execution` - Whether to run the amplifiers serially or on one thread each
+    ///
+    /// Returns `None` if there are no phases, an amplifier faults or the chain never produces a signal
+    ///
+    /// # Examples
+    /// ```
+    /// use intcode::amplifiers::{AmplifierChain, Execution};
+    /// // Outputs its phase setting plus its input signal
+    /// let chain = AmplifierChain::new(vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99]);
+    /// assert_eq!(chain.run(&[1, 2, 3], false, Execution::Serial), Some(6));
+    /// assert_eq!(chain.run(&[], false, Execution::Serial), None);
+    /// ```
+    pub fn run(&self, phases: &[i64], feedback: bool, execution: Execution) -> Option<i64> {
+        if phases.is_empty() {
+            return None;
+        }
+        match execution {
+            Execution::Serial => self.run_serial(phases, feedback),
+            Execution::Threaded => self.run_threaded(phases, feedback),
+        }
+    }
+
+    /// Searches every permutation of the phase values for the highest thruster signal
+    ///
+    /// # Arguments
+    /// * `phase_values` - The distinct phase settings to hand out, one per amplifier
+    /// * `feedback`     - Whether the chain runs in feedback loop mode
+    /// * `execution`    - Whether to run the amplifiers serially or on one thread each
+    ///
+    /// Returns the maximal signal together with the phase settings that produced it
+    pub fn find_max_signal(&self, phase_values: &[i64], feedback: bool, execution: Execution) -> Option<(i64, Vec<i64>)> {
+        permutations(phase_values).into_iter()
+            .filter_map(|phases| self.run(&phases, feedback, execution).map(|signal| (signal, phases)))
+            .max_by_key(|(signal, _)| *signal)
+    }
+
+    /// Steps each amplifier in turn on the calling thread
+    fn run_serial(&self, phases: &[i64], feedback: bool) -> Option<i64> {
+        let mut amplifiers = phases.iter()
+            .map(|phase| {
+                let mut amplifier = Intcode::new(self.program.clone());
+                amplifier.push_input(*phase);
+                amplifier
+            })
+            .collect::<Vec<Intcode>>();
+
+        let mut signals = vec![0];
+        let mut thruster_signal = None;
+        loop {
+            for amplifier in amplifiers.iter_mut() {
+                signals.iter().for_each(|signal| amplifier.push_input(*signal));
+                if amplifier.run_until_blocked() == RunState::Faulted {
+                    return None;
+                }
+                signals = amplifier.drain_outputs();
+            }
+            if let Some(signal) = signals.last() {
+                thruster_signal = Some(*signal);
+            }
+
+            let halted = amplifiers.iter().all(|a| a.state() == RunState::Halted);
+            if !feedback || halted || signals.is_empty() {
+                return thruster_signal;
+            }
+        }
+    }
+
+    /// Runs every amplifier on its own thread connected by channels
+    ///
+    /// ## Note
+    /// The last amplifier always writes back to the calling thread, which records the thruster
+    /// signal and forwards it on to the first amplifier when running in feedback mode
+    fn run_threaded(&self, phases: &[i64], feedback: bool) -> Option<i64> {
+        let (thruster_tx, thruster_rx) = mpsc::channel();
+        let mut senders = Vec::new();
+        let mut receivers = Vec::new();
+        for phase in phases {
+            let (tx, rx) = mpsc::channel();
+            tx.send(*phase).ok()?;
+            senders.push(tx);
+            receivers.push(rx);
+        }
+        let first_tx = senders.first()?.clone();
+        first_tx.send(0).ok()?;
+
+        // Each amplifier writes to the next one's input, the last writes back to this thread
+        senders.remove(0);
+        senders.push(thruster_tx);
+        let handles = receivers.into_iter().zip(senders)
+            .map(|(receiver, sender)| {
+                let mut io = ChannelIo::new(receiver, sender);
+                let mut amplifier = Intcode::new(self.program.clone());
+                thread::spawn(move || amplifier.run_with_io(&mut io))
+            })
+            .collect::<Vec<_>>();
+
+        // Hanging up on the first amplifier straight away stops it waiting on more input
+        let feedback_tx = feedback.then_some(first_tx);
+        let mut thruster_signal = None;
+        for signal in thruster_rx {
+            thruster_signal = Some(signal);
+            if let Some(tx) = &feedback_tx {
+                let _ = tx.send(signal);
+            }
+        }
+        drop(feedback_tx);
+
+        let mut faulted = false;
+        for handle in handles {
+            faulted |= handle.join().ok()? == RunState::Faulted;
+        }
+        match faulted {
+            true => None,
+            false => thruster_signal
+        }
+    }
+}
+
+/// Generates every ordering of a set of values using Heap's algorithm
+///
+/// # Examples
+/// ```
+/// assert_eq!(intcode::amplifiers::permutations(&[1, 2]), vec![vec![1, 2], vec![2, 1]]);
+/// ```
+pub fn permutations(values: &[i64]) -> Vec<Vec<i64>> {
+    let mut current = values.to_vec();
+    let mut counters = vec![0; values.len()];
+    let mut result = vec![current.clone()];
+    let mut i = 0;
+    while i < current.len() {
+        if counters[i] < i {
+            match i % 2 {
+                0 => current.swap(0, i),
+                _ => current.swap(counters[i], i)
+            }
+            result.push(current.clone());
+            counters[i] += 1;
+            i = 0;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+    result
+}
+
+
+#[test]
+fn test_permutations() {
+    let mut result = permutations(&[1, 2, 3]);
+    result.sort();
+    assert_eq!(result, vec![vec![1, 2, 3], vec![1, 3, 2], vec![2, 1, 3], vec![2, 3, 1], vec![3, 1, 2], vec![3, 2, 1]]);
+    assert_eq!(permutations(&[0, 1, 2, 3, 4]).len(), 120);
+}
+
+#[test]
+fn test_run_chain() {
+    let chain = AmplifierChain::new(vec![3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0]);
+    for execution in [Execution::Serial, Execution::Threaded] {
+        assert_eq!(chain.run(&[4, 3, 2, 1, 0], false, execution), Some(43210));
+        assert_eq!(chain.find_max_signal(&[0, 1, 2, 3, 4], false, execution), Some((43210, vec![4, 3, 2, 1, 0])));
+    }
+
+    let chain = AmplifierChain::new(vec![3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23, 99, 0, 0]);
+    assert_eq!(chain.find_max_signal(&[0, 1, 2, 3, 4], false, Execution::Serial), Some((54321, vec![0, 1, 2, 3, 4])));
+
+    // Adds one to the signal, then waits on an input that never arrives without feedback
+    let chain = AmplifierChain::new(vec![3, 13, 3, 14, 1001, 14, 1, 14, 4, 14, 3, 13, 99, 0, 0]);
+    for execution in [Execution::Serial, Execution::Threaded] {
+        assert_eq!(chain.run(&[0, 1, 2, 3, 4], false, execution), Some(5));
+        assert_eq!(chain.run(&[], false, execution), None);
+        assert_eq!(chain.find_max_signal(&[], true, execution), None);
+    }
+}
+
+#[test]
+fn test_run_feedback_chain() {
+    let chain = AmplifierChain::new(vec![3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005, 28, 6, 99, 0, 0, 5]);
+    for execution in [Execution::Serial, Execution::Threaded] {
+        assert_eq!(chain.run(&[9, 8, 7, 6, 5], true, execution), Some(139629729));
+        assert_eq!(chain.find_max_signal(&[5, 6, 7, 8, 9], true, execution), Some((139629729, vec![9, 8, 7, 6, 5])));
+    }
+}
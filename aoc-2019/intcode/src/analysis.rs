@@ -0,0 +1,327 @@
+use crate::disassembler::{disassemble_at, Item, Line, Operand};
+use crate::instructions::op_code::OpCodeId;
+use crate::lexer;
+use crate::memory::Memory;
+use num_traits::FromPrimitive;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// How control moves from one basic block to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Execution continues with the instruction after the block
+    FallThrough,
+    /// A jump whose condition is an immediate value, so it is always taken
+    Jump,
+    /// A conditional jump that is taken
+    Taken,
+    /// A conditional jump that is not taken
+    NotTaken,
+}
+
+/// An edge between the basic blocks starting at two addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// A run of instructions that is only entered at its first instruction and only left after
+/// its last instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub lines: Vec<Line>,
+}
+
+/// An instruction that writes to an address holding a reachable instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeWrite {
+    /// Address of the writing instruction
+    pub instruction: usize,
+    /// Address that is written
+    pub address: usize,
+}
+
+/// Control flow graph of the code reachable from address zero
+///
+/// Jumps are only followed when their target is an immediate value, jumps through memory or
+/// the relative base are recorded as indirect since their target is only known at run time
+///
+/// # Examples
+/// ```
+/// use intcode::analysis::ControlFlowGraph;
+/// // Outputs the input until it is zero
+/// let graph = ControlFlowGraph::build(&[3, 9, 4, 9, 1005, 9, 0, 99, 0, 0]);
+/// assert_eq!(graph.blocks().iter().map(|b| b.start).collect::<Vec<usize>>(), vec![0, 7]);
+/// assert_eq!(graph.edges().len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    blocks: BTreeMap<usize, BasicBlock>,
+    edges: Vec<Edge>,
+    indirect_jumps: Vec<usize>,
+    invalid: Vec<usize>,
+    code_writes: Vec<CodeWrite>,
+}
+
+impl BasicBlock {
+    /// Gets the address after the last word of the block
+    pub fn end(&self) -> usize {
+        match self.lines.last() {
+            Some(line) => line.address + line.len(),
+            None => self.start
+        }
+    }
+}
+
+impl ControlFlowGraph {
+    /// Discovers the code reachable from address zero and splits it into basic blocks
+    ///
+    /// # Arguments
+    /// * `program` - The program as it is loaded, before it has modified itself
+    pub fn build(program: &[i64]) -> ControlFlowGraph {
+        let memory = Memory::new(program.to_vec());
+        let mut lines: BTreeMap<usize, Line> = BTreeMap::new();
+        let mut successors: BTreeMap<usize, Vec<(usize, EdgeKind)>> = BTreeMap::new();
+        let mut indirect_jumps = Vec::new();
+        let mut invalid = BTreeSet::new();
+
+        let mut pending = vec![0];
+        while let Some(address) = pending.pop() {
+            if lines.contains_key(&address) || invalid.contains(&address) {
+                continue;
+            }
+            let line = disassemble_at(&memory, address);
+            let (next, indirect) = match &line.item {
+                Item::Data(_) if address < memory.len() => {
+                    invalid.insert(address);
+                    continue;
+                },
+                Item::Data(_) => continue,
+                Item::Instruction { inputs, .. } => ControlFlowGraph::successors(&line, inputs)
+            };
+            if indirect {
+                indirect_jumps.push(address);
+            }
+            pending.extend(next.iter().map(|(to, _)| *to));
+            successors.insert(address, next);
+            lines.insert(address, line);
+        }
+
+        // Only keep the edges between decoded instructions
+        for next in successors.values_mut() {
+            next.retain(|(to, _)| lines.contains_key(to));
+        }
+        let mut predecessors: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (from, next) in &successors {
+            for (to, _) in next {
+                predecessors.entry(*to).or_default().push(*from);
+            }
+        }
+        let is_leader = |address: usize| -> bool {
+            match predecessors.get(&address).map(|p| p.as_slice()) {
+                Some([from]) => successors[from].len() != 1 || successors[from][0].1 != EdgeKind::FallThrough,
+                _ => true
+            }
+        };
+
+        let mut blocks = BTreeMap::new();
+        let mut edges = Vec::new();
+        for start in lines.keys().copied().filter(|a| is_leader(*a)) {
+            let mut block = BasicBlock { start, lines: Vec::new() };
+            let mut address = start;
+            loop {
+                block.lines.push(lines[&address].clone());
+                match successors[&address].as_slice() {
+                    [(next, EdgeKind::FallThrough)] if !is_leader(*next) => address = *next,
+                    next => {
+                        edges.extend(next.iter().map(|(to, kind)| Edge { from: start, to: *to, kind: *kind }));
+                        break;
+                    }
+                }
+            }
+            blocks.insert(start, block);
+        }
+
+        // Reachable words that do not decode yet count as code, the program may write the
+        // instruction it needs there before reaching them
+        let code = lines.values()
+            .flat_map(|line| line.address..line.address + line.len())
+            .chain(invalid.iter().copied())
+            .collect::<BTreeSet<usize>>();
+        let code_writes = lines.values()
+            .filter_map(|line| match &line.item {
+                Item::Instruction { output: Some(Operand::Position(address)), .. } => {
+                    let address = usize::try_from(*address).ok().filter(|a| code.contains(a))?;
+                    Some(CodeWrite { instruction: line.address, address })
+                },
+                _ => None
+            })
+            .collect();
+
+        indirect_jumps.sort_unstable();
+        ControlFlowGraph { blocks, edges, indirect_jumps, invalid: invalid.into_iter().collect(), code_writes }
+    }
+
+    /// Gets the basic blocks ordered by address
+    pub fn blocks(&self) -> Vec<&BasicBlock> {
+        self.blocks.values().collect()
+    }
+
+    /// Gets the basic block that contains the instruction starting at an address
+    pub fn block_containing(&self, address: usize) -> Option<&BasicBlock> {
+        self.blocks.values().find(|block| block.lines.iter().any(|line| line.address == address))
+    }
+
+    /// Gets the edges between basic blocks
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Gets the addresses of jumps whose target is only known at run time
+    pub fn indirect_jumps(&self) -> &[usize] {
+        &self.indirect_jumps
+    }
+
+    /// Gets reachable addresses that do not hold a valid instruction
+    pub fn invalid(&self) -> &[usize] {
+        &self.invalid
+    }
+
+    /// Gets the writes to fixed addresses that land inside reachable instructions, or on
+    /// reachable addresses that do not hold a valid instruction yet
+    ///
+    /// ## Note
+    /// Writes through the relative base can not be resolved statically and are not included
+    pub fn self_modifying_writes(&self) -> &[CodeWrite] {
+        &self.code_writes
+    }
+
+    /// Renders the graph in the Graphviz DOT language
+    ///
+    /// Blocks that are written to by the program are drawn in red and indirect jumps point
+    /// to a node labelled `?`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph intcode {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for block in self.blocks.values() {
+            let label = block.lines.iter().map(|line| line.to_string() + "\\l").collect::<String>();
+            let written = self.code_writes.iter().any(|w| (block.start..block.end()).contains(&w.address));
+            let color = match written {
+                true => ", color=red",
+                false => ""
+            };
+            let _ = writeln!(dot, "    b{} [label=\"{}\"{}];", block.start, label, color);
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::FallThrough => "",
+                EdgeKind::Jump => " [label=\"jump\"]",
+                EdgeKind::Taken => " [label=\"taken\"]",
+                EdgeKind::NotTaken => " [label=\"not taken\", style=dashed]",
+            };
+            let _ = writeln!(dot, "    b{} -> b{}{};", edge.from, edge.to, style);
+        }
+        if !self.indirect_jumps.is_empty() {
+            dot.push_str("    indirect [shape=plaintext, label=\"?\"];\n");
+        }
+        for address in &self.indirect_jumps {
+            if let Some(block) = self.block_containing(*address) {
+                let _ = writeln!(dot, "    b{} -> indirect [style=dotted];", block.start);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Helper function to find where control can go after an instruction
+    ///
+    /// Returns the known successors and whether the instruction also jumps to an unknown target
+    fn successors(line: &Line, inputs: &[Operand]) -> (Vec<(usize, EdgeKind)>, bool) {
+        let next = line.address + line.len();
+        let op_code = lexer::parse_instruction_type(line.words[0]).map(|i| i.op_code);
+        let jump_if = match op_code.and_then(FromPrimitive::from_i64) {
+            Some(OpCodeId::Complete) => return (Vec::new(), false),
+            Some(OpCodeId::JumpIfTrue) => true,
+            Some(OpCodeId::JumpIfFalse) => false,
+            _ => return (vec![(next, EdgeKind::FallThrough)], false)
+        };
+
+        let taken = match inputs[0] {
+            Operand::Immediate(value) => Some((value != 0) == jump_if),
+            _ => None
+        };
+        let target = match inputs[1] {
+            Operand::Immediate(value) => usize::try_from(value).ok(),
+            _ => None
+        };
+        match (taken, target) {
+            (Some(false), _) => (vec![(next, EdgeKind::FallThrough)], false),
+            (Some(true), Some(target)) => (vec![(target, EdgeKind::Jump)], false),
+            (Some(true), None) => (Vec::new(), true),
+            (None, Some(target)) => (vec![(target, EdgeKind::Taken), (next, EdgeKind::NotTaken)], false),
+            (None, None) => (vec![(next, EdgeKind::NotTaken)], true),
+        }
+    }
+}
+
+
+#[test]
+fn test_basic_blocks() {
+    // Counts down from the input, outputting each value, then halts
+    let program = crate::assembler::assemble("
+        in -> [n]
+        loop:   out [n]
+                add [n], #-1, [n]
+                jt [n], #loop
+                hlt
+        n:      data 0
+    ").unwrap();
+    let graph = ControlFlowGraph::build(&program);
+    let starts = graph.blocks().iter().map(|b| (b.start, b.lines.len())).collect::<Vec<(usize, usize)>>();
+    assert_eq!(starts, vec![(0, 1), (2, 3), (11, 1)]);
+    assert_eq!(graph.edges(), &[
+        Edge { from: 0, to: 2, kind: EdgeKind::FallThrough },
+        Edge { from: 2, to: 2, kind: EdgeKind::Taken },
+        Edge { from: 2, to: 11, kind: EdgeKind::NotTaken },
+    ]);
+    assert!(graph.self_modifying_writes().is_empty());
+    assert!(graph.indirect_jumps().is_empty());
+    assert_eq!(graph.block_containing(4).map(|b| b.start), Some(2));
+}
+
+#[test]
+fn test_jumps_and_self_modification() {
+    // Skips the data word with an unconditional jump, then jumps to the address stored at 9
+    let program = vec![1105, 1, 4, 12345, 106, 0, 9, 99, 0, 7];
+    let graph = ControlFlowGraph::build(&program);
+    assert_eq!(graph.blocks().iter().map(|b| b.start).collect::<Vec<usize>>(), vec![0, 4]);
+    assert_eq!(graph.edges(), &[Edge { from: 0, to: 4, kind: EdgeKind::Jump }]);
+    assert_eq!(graph.indirect_jumps(), &[4]);
+    assert!(graph.invalid().is_empty());
+
+    // Overwrites the target of its own jump
+    let program = vec![1101, 7, 0, 5, 1105, 1, 0];
+    let graph = ControlFlowGraph::build(&program);
+    assert_eq!(graph.self_modifying_writes(), &[CodeWrite { instruction: 0, address: 5 }]);
+    assert!(graph.to_dot().contains("b0 [label=\"0000: ADD #7, #0 -> [5]\\l0004: JT #1, #0\\l\", color=red];"));
+
+    // Writes the halt it is about to reach
+    let graph = ControlFlowGraph::build(&[1101, 90, 9, 4, 0]);
+    assert_eq!(graph.invalid(), &[4]);
+    assert_eq!(graph.self_modifying_writes(), &[CodeWrite { instruction: 0, address: 4 }]);
+}
+
+#[test]
+fn test_to_dot() {
+    let graph = ControlFlowGraph::build(&[3, 9, 4, 9, 1005, 9, 0, 99, 0, 0]);
+    assert_eq!(graph.to_dot(), "digraph intcode {
+    node [shape=box, fontname=\"monospace\"];
+    b0 [label=\"0000: IN -> [9]\\l0002: OUT [9]\\l0004: JT [9], #0\\l\"];
+    b7 [label=\"0007: HLT\\l\"];
+    b0 -> b0 [label=\"taken\"];
+    b0 -> b7 [label=\"not taken\", style=dashed];
+}
+");
+}
@@ -0,0 +1,212 @@
+use crate::io::IntcodeIo;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/// Highest value that is treated as an ASCII character code
+const MAX_ASCII: i64 = 127;
+
+/// A piece of decoded program output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// A line of text without its trailing newline
+    Line(String),
+    /// A value outside of the ASCII range, such as a puzzle answer
+    Value(i64),
+}
+
+/// Encodes text as input codes, one per character
+pub fn encode(text: &str) -> Vec<i64> {
+    text.chars().map(|c| c as i64).collect()
+}
+
+/// Encodes a line of text as input codes terminated by a newline
+///
+/// # Examples
+/// ```
+/// assert_eq!(intcode::ascii::encode_line("NOT A J\r\n"), vec![78, 79, 84, 32, 65, 32, 74, 10]);
+/// ```
+pub fn encode_line(line: &str) -> Vec<i64> {
+    let mut codes = encode(line.trim_end_matches(['\r', '\n']));
+    codes.push('\n' as i64);
+    codes
+}
+
+/// Encodes text as input codes a line at a time, so every line ends in a single newline
+/// whatever line endings the text uses
+///
+/// # Examples
+/// ```
+/// assert_eq!(intcode::ascii::encode_text("A\r\nB\nC"), vec![65, 10, 66, 10, 67]);
+/// ```
+pub fn encode_text(text: &str) -> Vec<i64> {
+    text.split_inclusive('\n')
+        .flat_map(|line| match line.ends_with('\n') {
+            true => encode_line(line),
+            false => encode(line)
+        })
+        .collect()
+}
+
+/// Writes an output value as a character, or as a number on its own line if it is not ASCII
+///
+/// # Examples
+/// ```
+/// let mut text = Vec::new();
+/// intcode::ascii::write_value(&mut text, 72).unwrap();
+/// intcode::ascii::write_value(&mut text, 19349722).unwrap();
+/// assert_eq!(text, b"H19349722\n");
+/// ```
+pub fn write_value(writer: &mut impl Write, value: i64) -> io::Result<()> {
+    match value {
+        0..=MAX_ASCII => write!(writer, "{}", value as u8 as char),
+        _ => writeln!(writer, "{}", value)
+    }
+}
+
+/// Splits output values into lines of text and values that are not ASCII
+///
+/// # Examples
+/// ```
+/// use intcode::ascii::{decode, Segment};
+/// let segments = decode(&[72, 105, 10, 19349722, 63]);
+/// assert_eq!(segments, vec![Segment::Line(String::from("Hi")), Segment::Value(19349722), Segment::Line(String::from("?"))]);
+/// ```
+pub fn decode(values: &[i64]) -> Vec<Segment> {
+    let mut decoder = AsciiDecoder::default();
+    let mut segments = values.iter().filter_map(|x| decoder.push(*x)).collect::<Vec<Segment>>();
+    segments.extend(decoder.finish());
+    segments
+}
+
+/// Incrementally decodes output values into lines of text
+#[derive(Debug, Clone, Default)]
+pub struct AsciiDecoder {
+    partial: String,
+}
+
+impl AsciiDecoder {
+    /// Adds an output value
+    ///
+    /// Returns a line once a newline is received, or the value itself if it is not ASCII.
+    /// A value that is not ASCII does not end the line being decoded
+    pub fn push(&mut self, value: i64) -> Option<Segment> {
+        match value {
+            10 => Some(Segment::Line(std::mem::take(&mut self.partial))),
+            0..=MAX_ASCII => {
+                self.partial.push(value as u8 as char);
+                None
+            },
+            _ => Some(Segment::Value(value))
+        }
+    }
+
+    /// Returns the text received since the last newline, such as a prompt
+    pub fn finish(&mut self) -> Option<Segment> {
+        match self.partial.is_empty() {
+            true => None,
+            false => Some(Segment::Line(std::mem::take(&mut self.partial)))
+        }
+    }
+}
+
+/// I/O that lets a person talk to an ASCII program through a terminal
+///
+/// Inputs are read a line at a time and fed to the program as character codes. Outputs are
+/// written as characters, except values that are not ASCII which are written as numbers on
+/// their own line
+///
+/// # Examples
+/// ```
+/// use intcode::ascii::AsciiIo;
+/// // Echoes a single character
+/// let mut program = vec![3, 0, 4, 0, 99];
+/// let mut io = AsciiIo::new("x\n".as_bytes(), Vec::new());
+/// intcode::run_interpreter(&mut program, &mut io).unwrap();
+/// assert_eq!(io.into_writer(), b"x");
+/// ```
+#[derive(Debug)]
+pub struct AsciiIo<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    pending: VecDeque<i64>,
+}
+
+impl<R: BufRead, W: Write> AsciiIo<R, W> {
+    /// Creates a new I/O that reads lines from `reader` and writes text to `writer`
+    pub fn new(reader: R, writer: W) -> AsciiIo<R, W> {
+        AsciiIo { reader, writer, pending: VecDeque::new() }
+    }
+
+    /// Queues a value to be read before the next line of the reader, such as a character code
+    /// of text that was prepared in advance
+    pub fn push_input(&mut self, value: i64) {
+        self.pending.push_back(value);
+    }
+
+    /// Consumes the I/O and returns the writer
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+}
+
+impl<R: BufRead, W: Write> IntcodeIo for AsciiIo<R, W> {
+    /// Reads the next character code, reading another line when the current one is used up
+    fn read(&mut self) -> Option<i64> {
+        if self.pending.is_empty() {
+            let _ = self.writer.flush();
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => self.pending.extend(encode_line(&line))
+            }
+        }
+        self.pending.pop_front()
+    }
+
+    /// Writes a character, or a number on its own line for values that are not ASCII
+    fn write(&mut self, value: i64) {
+        let _ = write_value(&mut self.writer, value);
+    }
+}
+
+
+#[test]
+fn test_decoder_keeps_partial_lines() {
+    let mut decoder = AsciiDecoder::default();
+    assert_eq!(encode("Go?").into_iter().filter_map(|x| decoder.push(x)).count(), 0);
+    assert_eq!(decoder.push(-1), Some(Segment::Value(-1)));
+    assert_eq!(decoder.push(10), Some(Segment::Line(String::from("Go?"))));
+    assert_eq!(decoder.push(10), Some(Segment::Line(String::new())));
+    assert_eq!(decoder.finish(), None);
+}
+
+#[test]
+fn test_ascii_io() {
+    use crate::machine::{Intcode, RunState};
+
+    // Reads characters until a newline, then outputs how many were read and the last one
+    let program = crate::assembler::assemble("
+        loop:   in -> [char]
+                eq [char], #10, [done]
+                jt [done], #end
+                add [count], #1000, [count]
+                add [char], #0, [last]
+                jf #0, #loop
+        end:    out [count]
+                out [last]
+                out #10
+                hlt
+        char:   data 0
+        done:   data 0
+        count:  data 0
+        last:   data 0
+    ").unwrap();
+    let mut io = AsciiIo::new("abc\r\nignored\n".as_bytes(), Vec::new());
+    assert_eq!(Intcode::new(program.clone()).run_with_io(&mut io), RunState::Halted);
+    assert_eq!(String::from_utf8(io.into_writer()).unwrap(), "3000\nc\n");
+
+    let mut io = AsciiIo::new("ignored\n".as_bytes(), Vec::new());
+    encode_text("de\r\n").into_iter().for_each(|x| io.push_input(x));
+    assert_eq!(Intcode::new(program).run_with_io(&mut io), RunState::Halted);
+    assert_eq!(String::from_utf8(io.into_writer()).unwrap(), "2000\ne\n");
+}
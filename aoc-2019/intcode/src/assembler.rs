@@ -0,0 +1,259 @@
+use crate::disassembler::get_mnemonic;
+use crate::instructions::op_code::OpCodeId;
+use crate::parameters::ParameterMode;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Op code and number of parameters of every instruction
+const INSTRUCTIONS: [(OpCodeId, usize); 10] = [
+    (OpCodeId::Add, 3),
+    (OpCodeId::Multiply, 3),
+    (OpCodeId::Input, 1),
+    (OpCodeId::Output, 1),
+    (OpCodeId::JumpIfTrue, 2),
+    (OpCodeId::JumpIfFalse, 2),
+    (OpCodeId::LessThan, 3),
+    (OpCodeId::Equals, 3),
+    (OpCodeId::AdjustRelativeBase, 1),
+    (OpCodeId::Complete, 0),
+];
+
+/// Error raised when a source line can not be assembled, lines are one based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+/// A literal value or a reference to the address of a label
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(i64),
+    Label(String),
+}
+
+/// An operand together with the addressing mode given by its sigil
+#[derive(Debug, Clone, PartialEq)]
+struct Operand {
+    mode: ParameterMode,
+    value: Value,
+}
+
+/// A parsed source line that emits words
+#[derive(Debug)]
+enum Statement {
+    Instruction { op_code: i64, operands: Vec<Operand> },
+    Data(Vec<Value>),
+}
+
+/// Assembles a program written with instruction mnemonics into intcode
+///
+/// ## Syntax
+/// * One instruction per line: `add`, `mul`, `in`, `out`, `jt`, `jf`, `lt`, `eq`, `arb` or `hlt`
+/// * Operands are separated by commas, the output operand may be written after `->` instead
+/// * `#5` is an immediate operand, `[5]` or `5` a position operand and `[rb+5]` a relative operand
+/// * `name:` defines a label, labels can be used wherever a number can
+/// * `data 1, 2, label` emits raw words
+/// * `;` starts a comment and a leading `0004:` address, as printed by the disassembler, is ignored
+///
+/// # Examples
+/// ```
+/// let program = intcode::assembler::assemble("
+///     in -> [value]
+///     out [value]
+///     hlt
+/// value: data 0
+/// ").unwrap();
+/// assert_eq!(program, vec![3, 5, 4, 5, 99, 0]);
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssemblyError> {
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    let mut statements: Vec<(usize, Statement)> = Vec::new();
+    let mut address = 0;
+
+    for (i, raw_line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: String| AssemblyError { line: line_number, message };
+        let mut line = raw_line.split(';').next().unwrap_or("").trim();
+
+        while let Some((prefix, rest)) = line.split_once(':') {
+            let prefix = prefix.trim();
+            if prefix.chars().all(|c| c.is_ascii_digit()) && !prefix.is_empty() {
+                // Address annotation from a disassembly listing
+            } else if is_identifier(prefix) {
+                if labels.insert(prefix.to_string(), address).is_some() {
+                    return Err(error(format!("label {:?} is defined more than once", prefix)));
+                }
+            } else {
+                break;
+            }
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = match line.split_once(char::is_whitespace) {
+            Some((m, r)) => (m, r.trim()),
+            None => (line, "")
+        };
+        let mnemonic = mnemonic.to_lowercase();
+        let fields = split_operands(rest);
+
+        let statement = if mnemonic == "data" {
+            if fields.is_empty() {
+                return Err(error(String::from("data directive needs at least one value")));
+            }
+            let values = fields.iter().map(|f| parse_value(f).map_err(&error)).collect::<Result<Vec<Value>, AssemblyError>>()?;
+            address += values.len() as i64;
+            Statement::Data(values)
+        } else {
+            let (op_code, parameter_count) = match INSTRUCTIONS.iter().find(|(op, _)| get_mnemonic(*op).0.eq_ignore_ascii_case(&mnemonic)) {
+                Some((op_code, count)) => (*op_code, *count),
+                None => return Err(error(format!("unknown mnemonic {:?}", mnemonic)))
+            };
+            if fields.len() != parameter_count {
+                return Err(error(format!("{} takes {} operands but {} were given", mnemonic, parameter_count, fields.len())));
+            }
+            let operands = fields.iter().map(|f| parse_operand(f).map_err(&error)).collect::<Result<Vec<Operand>, AssemblyError>>()?;
+            let (_, writes_output) = get_mnemonic(op_code);
+            if writes_output && operands.last().map(|o| o.mode) == Some(ParameterMode::Immediate) {
+                return Err(error(format!("the output operand of {} can not be immediate", mnemonic)));
+            }
+            address += 1 + operands.len() as i64;
+            Statement::Instruction { op_code: op_code as i64, operands }
+        };
+        statements.push((line_number, statement));
+    }
+
+    let mut program = Vec::new();
+    for (line_number, statement) in statements {
+        let resolve = |value: &Value| match value {
+            Value::Number(n) => Ok(*n),
+            Value::Label(name) => labels.get(name).copied()
+                                        .ok_or_else(|| AssemblyError { line: line_number, message: format!("undefined label {:?}", name) }),
+        };
+        match statement {
+            Statement::Data(values) => {
+                for value in &values {
+                    program.push(resolve(value)?);
+                }
+            },
+            Statement::Instruction { op_code, operands } => {
+                let modes = operands.iter().enumerate()
+                    .map(|(i, o)| (o.mode as i64) * 10_i64.pow(i as u32 + 2))
+                    .sum::<i64>();
+                program.push(op_code + modes);
+                for operand in &operands {
+                    program.push(resolve(&operand.value)?);
+                }
+            }
+        }
+    }
+    Ok(program)
+}
+
+/// Helper function to split an operand list on commas and the `->` output marker
+fn split_operands(operands: &str) -> Vec<&str> {
+    operands.split(',')
+            .flat_map(|x| x.split("->"))
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect()
+}
+
+/// Helper function to parse a single operand and its addressing mode sigil
+fn parse_operand(field: &str) -> Result<Operand, String> {
+    if let Some(rest) = field.strip_prefix('#') {
+        return Ok(Operand { mode: ParameterMode::Immediate, value: parse_value(rest.trim())? });
+    }
+    let inner = match field.strip_prefix('[') {
+        Some(rest) => rest.strip_suffix(']').ok_or(format!("missing closing bracket in {:?}", field))?.trim(),
+        None => field
+    };
+    match inner.strip_prefix("rb") {
+        Some(offset) if !is_identifier(inner) || inner == "rb" => {
+            let offset = offset.replace(' ', "");
+            let value = match offset.as_str() {
+                "" => 0,
+                _ => offset.strip_prefix('+').unwrap_or(&offset).parse::<i64>()
+                           .map_err(|_| format!("invalid relative offset in {:?}", field))?
+            };
+            Ok(Operand { mode: ParameterMode::Relative, value: Value::Number(value) })
+        },
+        _ => Ok(Operand { mode: ParameterMode::Position, value: parse_value(inner)? })
+    }
+}
+
+/// Helper function to parse a number or a label reference
+fn parse_value(field: &str) -> Result<Value, String> {
+    if let Ok(n) = field.parse::<i64>() {
+        Ok(Value::Number(n))
+    } else if is_identifier(field) {
+        Ok(Value::Label(field.to_string()))
+    } else {
+        Err(format!("expected a number or a label but found {:?}", field))
+    }
+}
+
+/// Helper function to check if a token is a valid label name
+fn is_identifier(token: &str) -> bool {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
+
+
+#[test]
+fn test_assemble_operand_modes() {
+    let program = assemble("ADD [12], #3 -> [7]\nlt [rb-2], #5, rb+3\nout [rb]\nIn 9\nhlt").unwrap();
+    assert_eq!(program, vec![1001, 12, 3, 7, 21207, -2, 5, 3, 204, 0, 3, 9, 99]);
+}
+
+#[test]
+fn test_assemble_labels_and_data() {
+    let source = "
+        ; counts down from 3 and prints each value
+        start:  out [counter]
+                add [counter], #-1, [counter]
+                jt [counter], #start    ; loop while non zero
+                hlt
+        counter: data 3
+        table: data 1, -2, counter
+    ";
+    let program = assemble(source).unwrap();
+    assert_eq!(program, vec![4, 10, 1001, 10, -1, 10, 1005, 10, 0, 99, 3, 1, -2, 10]);
+}
+
+#[test]
+fn test_assemble_round_trips_with_disassembler() {
+    let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8, 21107, -2, 5, 3, 12345];
+    let listing = crate::disassembler::listing(&program);
+    assert_eq!(assemble(&listing), Ok(program));
+
+    // Words that can not execute, such as writes to immediate operands, reassemble as data
+    for program in [vec![11101, 1, 1, 1, 99, 103, 5, 99], vec![21107, 1, 2, 3, 11108, 1, 1, 0, 10003, 7], vec![-1, 30001, 1105]] {
+        let listing = crate::disassembler::listing(&program);
+        assert_eq!(assemble(&listing), Ok(program), "{}", listing);
+    }
+}
+
+#[test]
+fn test_assemble_errors() {
+    assert_eq!(assemble("add #1, #2\n"), Err(AssemblyError { line: 1, message: String::from("add takes 3 operands but 2 were given") }));
+    assert_eq!(assemble("hlt\nfoo #1"), Err(AssemblyError { line: 2, message: String::from("unknown mnemonic \"foo\"") }));
+    assert_eq!(assemble("\n\nin #4"), Err(AssemblyError { line: 3, message: String::from("the output operand of in can not be immediate") }));
+    assert_eq!(assemble("jt #1, #nowhere"), Err(AssemblyError { line: 1, message: String::from("undefined label \"nowhere\"") }));
+    assert_eq!(assemble("a: hlt\na: hlt").unwrap_err().line, 2);
+    assert_eq!(assemble("out [5").unwrap_err().line, 1);
+}
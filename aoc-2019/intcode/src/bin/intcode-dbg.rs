@@ -0,0 +1,38 @@
+use intcode::debugger::Debugger;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+
+/// Step debugger for intcode programs
+///
+/// Runs the commands in a script file when one is given, otherwise starts an interactive session
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let (filename, script) = match args.as_slice() {
+        [filename] => (filename, None),
+        [filename, flag, script] if flag == "--script" => (filename, Some(script)),
+        _ => {
+            eprintln!("Usage: intcode-dbg <program file> [--script <command file>]");
+            process::exit(2);
+        }
+    };
+
+    let program = match intcode::load_program_from_file(filename) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Could not load {}: {}", filename, e);
+            process::exit(1);
+        }
+    };
+
+    let mut debugger = Debugger::new(program);
+    let result = match script {
+        Some(script) => File::open(script)
+            .and_then(|f| debugger.run_script(BufReader::new(f), &mut io::stdout())),
+        None => debugger.run_interactive()
+    };
+    if let Err(e) = result {
+        eprintln!("Debugger session failed: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,25 @@
+use intcode::analysis::ControlFlowGraph;
+use std::process;
+
+/// Prints an annotated disassembly listing of an intcode program file, or its control flow
+/// graph in the Graphviz DOT language
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let (filename, dot) = match args.as_slice() {
+        [filename] => (filename, false),
+        [filename, flag] if flag == "--dot" => (filename, true),
+        _ => {
+            eprintln!("Usage: intcode-dis <program file> [--dot]");
+            process::exit(2);
+        }
+    };
+
+    match intcode::load_program_from_file(filename) {
+        Ok(program) if dot => print!("{}", ControlFlowGraph::build(&program).to_dot()),
+        Ok(program) => print!("{}", intcode::disassembler::listing(&program)),
+        Err(e) => {
+            eprintln!("Could not load {}: {}", filename, e);
+            process::exit(1);
+        }
+    }
+}
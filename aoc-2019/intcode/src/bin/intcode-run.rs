@@ -0,0 +1,372 @@
+use intcode::ascii::{self, AsciiIo};
+use intcode::error::IntcodeError;
+use intcode::io::IntcodeIo;
+use intcode::loader::LoadError;
+use intcode::machine::{Backend, ExitReason, Intcode, RunOutcome};
+use intcode::memory::Memory;
+use intcode::trace::{NoTrace, WriteLog};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: intcode-run [options] <program file | ->
+
+Runs an intcode program, reading it from stdin when the file is -
+
+Options:
+  -i, --input VALUES       queue comma separated input values, may be repeated
+  -f, --input-file FILE    queue the input values in FILE
+  -s, --set ADDR=VALUE     write VALUE to ADDR before running, may be repeated
+  -o, --output FORMAT      print outputs as decimal (default), ascii or json
+      --ascii              read inputs as lines of text and print outputs as ascii
+      --no-stdin           fail instead of reading more input from stdin
+      --max-steps N        stop after executing N instructions
+      --backend NAME       execute with the interpreter (default) or threaded code
+      --dump-memory RANGE  print the final memory cells in START..END, START.. or all
+      --diff-against FILE  print the final memory cells that differ from the memory in FILE,
+                           and which instruction last wrote each of them
+      --save-memory FILE   write the final memory to FILE, to diff another run against it
+
+Queued inputs are read first, then further inputs are read from stdin unless the program
+itself was read from stdin. In ascii mode input files and stdin are read as text.
+Memory dumps and diffs are printed to stderr once the program stops.
+
+Exit codes: 0 halted, 1 could not load, 2 bad usage, 3 faulted, ran out of input or hit the step limit";
+
+/// How outputs are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Decimal,
+    Ascii,
+    Json,
+}
+
+/// Parsed command line options
+#[derive(Debug, PartialEq)]
+struct Options {
+    program: String,
+    inputs: Vec<i64>,
+    input_files: Vec<String>,
+    patches: Vec<(i64, i64)>,
+    format: OutputFormat,
+    ascii_input: bool,
+    stdin_input: bool,
+    max_steps: Option<u64>,
+    backend: Backend,
+    dump: Option<(usize, Option<usize>)>,
+    diff_against: Option<String>,
+    save_memory: Option<String>,
+}
+
+/// Number of cells on each row of a memory dump
+const DUMP_COLUMNS: usize = 10;
+
+/// I/O that reads queued inputs then stdin, and prints outputs in the chosen format
+///
+/// `--ascii` runs on `ascii::AsciiIo` instead
+struct RunnerIo {
+    queued: VecDeque<i64>,
+    stdin: Option<io::StdinLock<'static>>,
+    format: OutputFormat,
+    outputs: Vec<i64>,
+}
+
+impl IntcodeIo for RunnerIo {
+    fn read(&mut self) -> Option<i64> {
+        while self.queued.is_empty() {
+            let stdin = self.stdin.as_mut()?;
+            let _ = io::stdout().flush();
+            let mut line = String::new();
+            match stdin.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => match parse_values(&line) {
+                    Ok(values) => self.queued.extend(values),
+                    Err(e) => eprintln!("{}", e)
+                }
+            }
+        }
+        self.queued.pop_front()
+    }
+
+    fn write(&mut self, value: i64) {
+        match self.format {
+            OutputFormat::Decimal => println!("{}", value),
+            OutputFormat::Ascii => {
+                let _ = ascii::write_value(&mut io::stdout(), value);
+            },
+            OutputFormat::Json => self.outputs.push(value),
+        }
+    }
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let program = match load(&options) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Could not load program: {}", e);
+            process::exit(1);
+        }
+    };
+    let mut queued = VecDeque::from(options.inputs.clone());
+    for filename in &options.input_files {
+        let values = std::fs::read_to_string(filename)
+            .map_err(|e| e.to_string())
+            .and_then(|text| match options.ascii_input {
+                true => Ok(ascii::encode_text(&text)),
+                false => parse_values(&text)
+            });
+        match values {
+            Ok(values) => queued.extend(values),
+            Err(e) => {
+                eprintln!("Could not load inputs from {}: {}", filename, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut machine = Intcode::new(program).with_backend(options.backend);
+    for (address, value) in &options.patches {
+        if let Err(e) = machine.memory_mut().write(*address, *value) {
+            eprintln!("Could not set address {}: {}", address, e);
+            process::exit(1);
+        }
+    }
+
+    let reads_stdin = options.stdin_input && options.program != "-";
+    let mut log = WriteLog::default();
+    let outcome = match options.ascii_input {
+        true => {
+            let stdin: Box<dyn BufRead> = match reads_stdin {
+                true => Box::new(io::stdin().lock()),
+                false => Box::new(io::empty())
+            };
+            let mut io = AsciiIo::new(stdin, io::stdout());
+            queued.into_iter().for_each(|x| io.push_input(x));
+            run(&mut machine, &mut io, &options, &mut log)
+        },
+        false => {
+            let mut io = RunnerIo {
+                queued,
+                stdin: reads_stdin.then(|| io::stdin().lock()),
+                format: options.format,
+                outputs: Vec::new(),
+            };
+            let outcome = run(&mut machine, &mut io, &options, &mut log);
+            if options.format == OutputFormat::Json {
+                let json = serde_json::json!({ "outputs": io.outputs, "state": format!("{:?}", machine.state()), "steps": outcome.steps });
+                println!("{}", json);
+            }
+            outcome
+        }
+    };
+    let _ = io::stdout().flush();
+    if let Err(e) = inspect_memory(&options, machine.memory(), &log) {
+        eprintln!("Could not inspect memory: {}", e);
+        process::exit(1);
+    }
+
+    let error = match outcome.reason {
+        ExitReason::Halted => return,
+        ExitReason::Faulted(e) => e.to_string(),
+        ExitReason::AwaitingInput => {
+            let ip = outcome.instruction_pointer;
+            IntcodeError::InputExhausted { ip, word: machine.memory().get(ip) }.to_string()
+        },
+        ExitReason::StepLimit => {
+            let (ip, steps) = (outcome.instruction_pointer, outcome.steps);
+            IntcodeError::StepLimit { ip, word: machine.memory().get(ip), steps }.to_string()
+        }
+    };
+    eprintln!("Program stopped: {}", error);
+    process::exit(3);
+}
+
+/// Helper function to run the machine, recording writes only when they are diffed afterwards
+fn run(machine: &mut Intcode, io: &mut dyn IntcodeIo, options: &Options, log: &mut WriteLog) -> RunOutcome {
+    match options.diff_against {
+        Some(_) => machine.run_with_tracer(io, options.max_steps, log),
+        None => machine.run_with_tracer(io, options.max_steps, &mut NoTrace)
+    }
+}
+
+/// Helper function to load the program from its file or stdin
+fn load(options: &Options) -> Result<Vec<i64>, LoadError> {
+    match options.program.as_str() {
+        "-" => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Ok(intcode::load_program_from_str(&text)?)
+        },
+        filename => intcode::load_program_from_file(filename)
+    }
+}
+
+/// Helper function to dump, diff and save the final memory as requested by the options
+fn inspect_memory(options: &Options, memory: &Memory, log: &WriteLog) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some((start, end)) = options.dump {
+        let end = end.unwrap_or(memory.len()).min(memory.len()).max(start);
+        eprint!("{}", memory.dump(start, end - start, DUMP_COLUMNS));
+    }
+    if let Some(filename) = &options.diff_against {
+        let reference = Memory::new(intcode::load_program_from_file(filename)?);
+        let changes = reference.diff(memory);
+        eprintln!("{} cells differ from {}", changes.len(), filename);
+        for change in changes {
+            let writer = match log.last_write(change.address) {
+                Some(w) => format!("written at step {} by the instruction at {:04}", w.step, w.ip),
+                None => String::from("not written during the run")
+            };
+            eprintln!("{:04}: {} -> {}, {}", change.address, change.old, change.new, writer);
+        }
+    }
+    if let Some(filename) = &options.save_memory {
+        let text = memory.to_vec()?.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",");
+        std::fs::write(filename, text + "\n")?;
+    }
+    Ok(())
+}
+
+/// Helper function to parse the command line arguments, excluding the binary name
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        program: String::new(),
+        inputs: Vec::new(),
+        input_files: Vec::new(),
+        patches: Vec::new(),
+        format: OutputFormat::Decimal,
+        ascii_input: false,
+        stdin_input: true,
+        max_steps: None,
+        backend: Backend::Interpreter,
+        dump: None,
+        diff_against: None,
+        save_memory: None,
+    };
+    let mut program = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "-i" | "--input" => options.inputs.extend(parse_values(value()?)?),
+            "-f" | "--input-file" => options.input_files.push(value()?.clone()),
+            "-s" | "--set" => {
+                let patch = value()?;
+                let parsed = patch.split_once('=')
+                    .and_then(|(address, v)| Some((address.trim().parse::<i64>().ok()?, v.trim().parse::<i64>().ok()?)));
+                options.patches.push(parsed.ok_or(format!("expected ADDR=VALUE but found {:?}", patch))?);
+            },
+            "-o" | "--output" => options.format = match value()?.as_str() {
+                "decimal" => OutputFormat::Decimal,
+                "ascii" => OutputFormat::Ascii,
+                "json" => OutputFormat::Json,
+                other => return Err(format!("unknown output format {:?}", other))
+            },
+            "--ascii" => {
+                options.ascii_input = true;
+                options.format = OutputFormat::Ascii;
+            },
+            "--no-stdin" => options.stdin_input = false,
+            "--backend" => options.backend = match value()?.as_str() {
+                "interpreter" => Backend::Interpreter,
+                "threaded" => Backend::Threaded,
+                other => return Err(format!("unknown backend {:?}", other))
+            },
+            "--max-steps" => {
+                let steps = value()?;
+                options.max_steps = Some(steps.parse::<u64>().map_err(|_| format!("expected a step count but found {:?}", steps))?);
+            },
+            "--dump-memory" => options.dump = Some(parse_range(value()?)?),
+            "--diff-against" => options.diff_against = Some(value()?.clone()),
+            "--save-memory" => options.save_memory = Some(value()?.clone()),
+            flag if flag.starts_with('-') && flag != "-" => return Err(format!("unknown option {:?}", flag)),
+            file => match program {
+                None => program = Some(file.to_string()),
+                Some(_) => return Err(format!("unexpected argument {:?}", file))
+            }
+        }
+    }
+    options.program = program.ok_or("missing program file")?;
+    if options.ascii_input && options.format != OutputFormat::Ascii {
+        return Err(String::from("--ascii can not be combined with another output format"));
+    }
+    Ok(options)
+}
+
+/// Helper function to parse an address range, `all` or a range with an open end gives `None` as the end
+fn parse_range(text: &str) -> Result<(usize, Option<usize>), String> {
+    let error = || format!("expected START..END, START.. or all but found {:?}", text);
+    if text == "all" {
+        return Ok((0, None));
+    }
+    let (start, end) = text.split_once("..").ok_or_else(error)?;
+    let start = start.trim().parse::<usize>().map_err(|_| error())?;
+    match end.trim() {
+        "" => Ok((start, None)),
+        end => Ok((start, Some(end.parse::<usize>().map_err(|_| error())?)))
+    }
+}
+
+/// Helper function to parse values separated by commas or whitespace
+fn parse_values(text: &str) -> Result<Vec<i64>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse::<i64>().map_err(|_| format!("expected an integer but found {:?}", x)))
+        .collect()
+}
+
+
+#[test]
+fn test_parse_args() {
+    let args = ["-i", "1,2", "prog.txt", "--input", "3", "--set", "1=12", "-s", "2 = -2", "-o", "json", "--no-stdin", "--max-steps", "500"]
+        .iter().map(|x| x.to_string()).collect::<Vec<String>>();
+    let options = parse_args(&args).unwrap();
+    assert_eq!(options.program, "prog.txt");
+    assert_eq!(options.inputs, vec![1, 2, 3]);
+    assert_eq!(options.patches, vec![(1, 12), (2, -2)]);
+    assert_eq!(options.format, OutputFormat::Json);
+    assert!(!options.stdin_input);
+    assert_eq!(options.max_steps, Some(500));
+
+    let options = parse_args(&[String::from("--ascii"), String::from("-")]).unwrap();
+    assert_eq!((options.program.as_str(), options.format, options.ascii_input), ("-", OutputFormat::Ascii, true));
+    assert_eq!(options.backend, Backend::Interpreter);
+
+    let options = parse_args(&[String::from("--backend"), String::from("threaded"), String::from("a")]).unwrap();
+    assert_eq!(options.backend, Backend::Threaded);
+
+    let args = ["a", "--dump-memory", "4..12", "--diff-against", "b", "--save-memory", "c"].iter().map(|x| x.to_string()).collect::<Vec<String>>();
+    let options = parse_args(&args).unwrap();
+    assert_eq!((options.dump, options.diff_against.as_deref(), options.save_memory.as_deref()), (Some((4, Some(12))), Some("b"), Some("c")));
+    assert_eq!(parse_range("all"), Ok((0, None)));
+    assert_eq!(parse_range("8.."), Ok((8, None)));
+}
+
+#[test]
+fn test_parse_args_errors() {
+    let parse = |args: &[&str]| parse_args(&args.iter().map(|x| x.to_string()).collect::<Vec<String>>()).unwrap_err();
+    assert_eq!(parse(&[]), "missing program file");
+    assert_eq!(parse(&["a", "b"]), "unexpected argument \"b\"");
+    assert_eq!(parse(&["a", "--set", "12"]), "expected ADDR=VALUE but found \"12\"");
+    assert_eq!(parse(&["a", "-i"]), "-i needs a value");
+    assert_eq!(parse(&["a", "-i", "1,x"]), "expected an integer but found \"x\"");
+    assert_eq!(parse(&["a", "-o", "hex"]), "unknown output format \"hex\"");
+    assert_eq!(parse(&["a", "--backend", "jit"]), "unknown backend \"jit\"");
+    assert_eq!(parse(&["a", "--ascii", "-o", "json"]), "--ascii can not be combined with another output format");
+    assert_eq!(parse(&["a", "--fast"]), "unknown option \"--fast\"");
+    assert_eq!(parse(&["a", "--max-steps", "-1"]), "expected a step count but found \"-1\"");
+    assert_eq!(parse(&["a", "--dump-memory", "4-8"]), "expected START..END, START.. or all but found \"4-8\"");
+}
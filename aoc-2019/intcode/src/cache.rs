@@ -0,0 +1,84 @@
+use crate::instructions::{Instruction, MAX_INSTRUCTION_LENGTH, op_code::OpCode};
+
+/// Highest address that decoded instructions are cached for, code beyond it is decoded every time
+pub const MAX_CACHED_ADDRESS: usize = (1 << 16) - 1;
+
+/// Decoded instructions keyed by the address they start at
+///
+/// ## Note
+/// A write to memory must be reported with `invalidate` so that self modifying programs
+/// never execute a stale decode of an instruction that has since been overwritten
+///
+/// # Examples
+/// ```
+/// use intcode::{cache::DecodeCache, instructions::{Instruction, op_code::OpCode}};
+/// let mut cache = DecodeCache::default();
+/// cache.insert(0, Instruction::parse_from_slice(&[1101, 1, 2, 3]).unwrap());
+/// cache.invalidate(3);
+/// assert!(cache.get(0).is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DecodeCache {
+    entries: Vec<Option<Instruction>>,
+}
+
+impl DecodeCache {
+    /// Gets the decoded instruction starting at an address if it has been cached
+    pub fn get(&self, address: usize) -> Option<&Instruction> {
+        self.entries.get(address).and_then(|entry| entry.as_ref())
+    }
+
+    /// Caches the decoded instruction starting at an address
+    pub fn insert(&mut self, address: usize, instruction: Instruction) {
+        if address > MAX_CACHED_ADDRESS {
+            return;
+        }
+        if address >= self.entries.len() {
+            self.entries.resize(address + 1, None);
+        }
+        self.entries[address] = Some(instruction);
+    }
+
+    /// Drops every cached instruction that covers a memory address that has been written to
+    pub fn invalidate(&mut self, address: i64) {
+        let address = match usize::try_from(address) {
+            Ok(a) => a,
+            Err(_) => return
+        };
+        let first = address.saturating_sub(MAX_INSTRUCTION_LENGTH - 1);
+        for start in first..(address + 1).min(self.entries.len()) {
+            let covers = self.entries[start].as_ref().is_some_and(|i| start + i.get_instruction_pointer_offset() as usize > address);
+            if covers {
+                self.entries[start] = None;
+            }
+        }
+    }
+
+    /// Drops every cached instruction
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+
+#[test]
+fn test_invalidate_covering_instructions() {
+    let mut cache = DecodeCache::default();
+    cache.invalidate(0);
+    cache.insert(0, Instruction::parse_from_slice(&[1101, 1, 2, 3]).unwrap());
+    cache.insert(4, Instruction::parse_from_slice(&[104, 1, 0, 0]).unwrap());
+    cache.insert(6, Instruction::parse_from_slice(&[99, 0, 0, 0]).unwrap());
+
+    cache.invalidate(5);
+    assert!(cache.get(0).is_some());
+    assert!(cache.get(4).is_none());
+    assert!(cache.get(6).is_some());
+
+    cache.invalidate(-1);
+    cache.invalidate(1 << 40);
+    cache.insert(1 << 40, Instruction::parse_from_slice(&[99, 0, 0, 0]).unwrap());
+    assert!(cache.get(6).is_some());
+
+    cache.clear();
+    assert!(cache.get(0).is_none());
+}
@@ -0,0 +1,310 @@
+use crate::disassembler::disassemble_at;
+use crate::machine::{Intcode, RunState};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, Write};
+
+/// Number of memory cells printed on each row of a memory dump
+const CELLS_PER_ROW: usize = 8;
+
+/// Interactive step debugger for an intcode machine
+///
+/// Commands are read one per line so that a session can be typed at a prompt or replayed
+/// from a file. Run the `help` command for the list of commands.
+///
+/// # Examples
+/// ```
+/// let mut debugger = intcode::debugger::Debugger::new(vec![104, 7, 99]);
+/// let mut transcript = Vec::new();
+/// debugger.run_script("continue\n".as_bytes(), &mut transcript).unwrap();
+/// assert!(String::from_utf8(transcript).unwrap().contains("output: 7"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    machine: Intcode,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeMap<i64, i64>,
+}
+
+/// Result of executing a single debugger command
+#[derive(Debug, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    Quit,
+}
+
+const HELP: &str = "\
+break ADDR          stop before executing the instruction at ADDR
+delete ADDR         remove the breakpoint or watchpoint at ADDR
+watch ADDR          stop after the value of the memory cell at ADDR changes
+step [N]            execute N instructions, one by default
+continue            run until a breakpoint, watchpoint, halt, fault or missing input
+regs                print the instruction pointer, relative base and run state
+mem ADDR [COUNT]    print COUNT memory cells starting at ADDR
+dis [ADDR] [COUNT]  disassemble COUNT instructions starting at ADDR, the ip by default
+poke ADDR VALUE     write VALUE to the memory cell at ADDR
+input VALUE...      queue values for the input instruction
+quit                end the session";
+
+impl Debugger {
+    /// Creates a new debugger for a program, stopped before the first instruction
+    pub fn new(program: Vec<i64>) -> Debugger {
+        Debugger::with_machine(Intcode::new(program))
+    }
+
+    /// Creates a new debugger for an existing machine
+    pub fn with_machine(machine: Intcode) -> Debugger {
+        Debugger { machine, breakpoints: BTreeSet::new(), watchpoints: BTreeMap::new() }
+    }
+
+    /// Gets the machine being debugged
+    pub fn machine(&self) -> &Intcode {
+        &self.machine
+    }
+
+    /// Reads commands until the input ends or a `quit` command, writing responses to `output`
+    ///
+    /// # Arguments
+    /// * `input`  - Source of commands, one per line, blank lines and `;` comments are skipped
+    /// * `output` - Destination for the session transcript
+    pub fn run_script(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            if self.execute(&line?, output)? == Flow::Quit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs an interactive session on stdin and stdout with a prompt before each command
+    pub fn run_interactive(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let mut stdout = io::stdout();
+        loop {
+            write!(stdout, "(dbg) ")?;
+            stdout.flush()?;
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line)? == 0 || self.execute(&line, &mut stdout)? == Flow::Quit {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Helper function to parse and execute a single command line
+    fn execute(&mut self, line: &str, output: &mut impl Write) -> io::Result<Flow> {
+        let line = line.split(';').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let command = match tokens.next() {
+            Some(c) => c,
+            None => return Ok(Flow::Continue)
+        };
+        let arguments = match tokens.map(|t| t.parse::<i64>()).collect::<Result<Vec<i64>, _>>() {
+            Ok(a) => a,
+            Err(_) => {
+                writeln!(output, "error: arguments must be integers")?;
+                return Ok(Flow::Continue);
+            }
+        };
+        let address = |i: usize| arguments.get(i).and_then(|a| usize::try_from(*a).ok());
+
+        match (command, arguments.len()) {
+            ("break" | "b", 1) => match address(0) {
+                Some(a) => {
+                    self.breakpoints.insert(a);
+                    writeln!(output, "breakpoint set at {:04}", a)?;
+                },
+                None => writeln!(output, "error: address must not be negative")?
+            },
+            ("delete", 1) => {
+                let removed_break = address(0).is_some_and(|a| self.breakpoints.remove(&a));
+                let removed_watch = self.watchpoints.remove(&arguments[0]).is_some();
+                match removed_break || removed_watch {
+                    true => writeln!(output, "deleted {:04}", arguments[0])?,
+                    false => writeln!(output, "error: nothing set at {}", arguments[0])?
+                }
+            },
+            ("watch" | "w", 1) => match self.machine.memory().read(arguments[0]) {
+                Ok(value) => {
+                    self.watchpoints.insert(arguments[0], value);
+                    writeln!(output, "watchpoint set at {:04} = {}", arguments[0], value)?;
+                },
+                Err(e) => writeln!(output, "error: {}", e)?
+            },
+            ("step" | "s", 0 | 1) => {
+                let count = arguments.first().copied().unwrap_or(1).max(1);
+                for _ in 0..count {
+                    if !self.step(output)? {
+                        break;
+                    }
+                }
+                self.print_location(output)?;
+            },
+            ("continue" | "c", 0) => {
+                while self.step(output)? && !self.breakpoints.contains(&self.machine.instruction_pointer()) {}
+                if self.breakpoints.contains(&self.machine.instruction_pointer()) && self.machine.state() == RunState::Running {
+                    writeln!(output, "breakpoint at {:04}", self.machine.instruction_pointer())?;
+                }
+                self.print_location(output)?;
+            },
+            ("regs" | "r", 0) => writeln!(output, "ip={:04} rb={} state={:?}", self.machine.instruction_pointer(),
+                                          self.machine.relative_base(), self.machine.state())?,
+            ("mem" | "x", 1 | 2) => match address(0) {
+                Some(start) if start >= self.machine.memory().len() => {
+                    writeln!(output, "error: address {} is past the end of memory ({} cells)", start, self.machine.memory().len())?
+                },
+                Some(start) => {
+                    let available = self.machine.memory().len() - start;
+                    let count = arguments.get(1).map_or(CELLS_PER_ROW, |c| usize::try_from(*c).unwrap_or(0)).min(available);
+                    let cells = self.machine.memory().read_range(start, count);
+                    for (row, chunk) in cells.chunks(CELLS_PER_ROW).enumerate() {
+                        let values = chunk.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+                        writeln!(output, "{:04}: {}", start + row * CELLS_PER_ROW, values.join(" "))?;
+                    }
+                },
+                None => writeln!(output, "error: address must not be negative")?
+            },
+            ("dis" | "d", 0..=2) => match arguments.first().map_or(Some(self.machine.instruction_pointer()), |_| address(0)) {
+                Some(start) if start >= self.machine.memory().len() => {
+                    writeln!(output, "error: address {} is past the end of memory ({} cells)", start, self.machine.memory().len())?
+                },
+                Some(mut start) => {
+                    let count = arguments.get(1).map_or(5, |c| (*c).max(0));
+                    for _ in 0..count {
+                        if start >= self.machine.memory().len() {
+                            break;
+                        }
+                        let line = disassemble_at(self.machine.memory(), start);
+                        let marker = match start == self.machine.instruction_pointer() {
+                            true => "=>",
+                            false => "  "
+                        };
+                        writeln!(output, "{} {}", marker, line)?;
+                        start += line.len();
+                    }
+                },
+                None => writeln!(output, "error: address must not be negative")?
+            },
+            ("poke" | "p", 2) => match self.machine.memory_mut().write(arguments[0], arguments[1]) {
+                Ok(()) => writeln!(output, "{:04} = {}", arguments[0], arguments[1])?,
+                Err(e) => writeln!(output, "error: {}", e)?
+            },
+            ("input" | "i", _) if !arguments.is_empty() => {
+                arguments.iter().for_each(|x| self.machine.push_input(*x));
+                writeln!(output, "queued {} input(s)", arguments.len())?;
+            },
+            ("quit" | "q", 0) => return Ok(Flow::Quit),
+            ("help" | "h", 0) => writeln!(output, "{}", HELP)?,
+            _ => writeln!(output, "error: unknown command {:?}, try help", line)?
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Helper function to execute one instruction and report outputs and stop reasons
+    ///
+    /// Returns false if the machine stopped or a watchpoint triggered
+    fn step(&mut self, output: &mut impl Write) -> io::Result<bool> {
+        let state = self.machine.step();
+        for value in self.machine.drain_outputs() {
+            writeln!(output, "output: {}", value)?;
+        }
+
+        let mut triggered = false;
+        for (address, value) in self.watchpoints.iter_mut() {
+            let current = self.machine.memory().read(*address).unwrap_or(0);
+            if current != *value {
+                writeln!(output, "watchpoint {:04}: {} -> {}", address, value, current)?;
+                *value = current;
+                triggered = true;
+            }
+        }
+
+        match state {
+            RunState::Running => (),
+            RunState::Halted => writeln!(output, "halted")?,
+            RunState::AwaitingInput => writeln!(output, "awaiting input")?,
+            RunState::Faulted => match self.machine.error() {
+                Some(e) => writeln!(output, "faulted: {}", e)?,
+                None => writeln!(output, "faulted")?
+            }
+        }
+        Ok(state == RunState::Running && !triggered)
+    }
+
+    /// Helper function to print the next instruction to be executed
+    fn print_location(&self, output: &mut impl Write) -> io::Result<()> {
+        let line = disassemble_at(self.machine.memory(), self.machine.instruction_pointer());
+        writeln!(output, "=> {}", line)
+    }
+}
+
+
+#[test]
+fn test_replay_script() {
+    // Outputs 1 if the input is equal to 8, otherwise 0
+    let mut debugger = Debugger::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8]);
+    let script = "
+        break 6
+        watch 9     ; the input is stored here
+        continue
+        input 8
+        continue
+        continue
+        regs
+        mem 8 3
+        step
+        step
+    ";
+    let mut transcript = Vec::new();
+    debugger.run_script(script.as_bytes(), &mut transcript).unwrap();
+    assert_eq!(String::from_utf8(transcript).unwrap(), "\
+breakpoint set at 0006
+watchpoint set at 0009 = -1
+awaiting input
+=> 0000: IN -> [9]
+queued 1 input(s)
+watchpoint 0009: -1 -> 8
+=> 0002: EQ [9], [10] -> [9]
+watchpoint 0009: 8 -> 1
+breakpoint at 0006
+=> 0006: OUT [9]
+ip=0006 rb=0 state=Running
+0008: 99 1 8
+output: 1
+=> 0008: HLT
+halted
+=> 0008: HLT
+");
+}
+
+#[test]
+fn test_poke_and_disassemble() {
+    let mut debugger = Debugger::new(vec![1101, 1, 2, 5, 99, 0]);
+    let mut transcript = Vec::new();
+    debugger.run_script("poke 1 40\ndis 0 2\nstep 5\nmem 5 1\nquit\nregs\n".as_bytes(), &mut transcript).unwrap();
+    assert_eq!(String::from_utf8(transcript).unwrap(), "\
+0001 = 40
+=> 0000: ADD #40, #2 -> [5]
+   0004: HLT
+halted
+=> 0004: HLT
+0005: 42
+");
+    assert_eq!(debugger.machine().state(), RunState::Halted);
+}
+
+#[test]
+fn test_command_errors() {
+    let mut debugger = Debugger::new(vec![99]);
+    let mut transcript = Vec::new();
+    debugger.run_script("jump 4\nbreak x\nbreak -1\ndelete 3\nmem 4 1\nmem 0 9223372036854775807\ndis -1\ndis 0 9223372036854775807\ndis 3\n".as_bytes(), &mut transcript).unwrap();
+    assert_eq!(String::from_utf8(transcript).unwrap(), "\
+error: unknown command \"jump 4\", try help
+error: arguments must be integers
+error: address must not be negative
+error: nothing set at 3
+error: address 4 is past the end of memory (1 cells)
+0000: 99
+error: address must not be negative
+=> 0000: HLT
+error: address 3 is past the end of memory (1 cells)
+");
+}
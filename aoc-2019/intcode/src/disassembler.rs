@@ -0,0 +1,216 @@
+use crate::instructions::{Instruction, MAX_INSTRUCTION_LENGTH, op_code::{OpCode, OpCodeId}};
+use crate::lexer::{self, Parameter};
+use crate::memory::Memory;
+use crate::parameters::ParameterMode;
+use num_traits::FromPrimitive;
+use std::fmt;
+
+/// An instruction operand annotated with its addressing mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// Rendered as `[12]`
+    Position(i64),
+    /// Rendered as `#3`
+    Immediate(i64),
+    /// Rendered as `[rb+5]` or `[rb-5]`
+    Relative(i64),
+}
+
+/// A decoded instruction or a word that does not decode as one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Instruction {
+        mnemonic: &'static str,
+        inputs: Vec<Operand>,
+        output: Option<Operand>,
+    },
+    Data(i64),
+}
+
+/// A single line of a disassembly listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub address: usize,
+    pub words: Vec<i64>,
+    pub item: Item,
+}
+
+impl Operand {
+    /// Creates an operand from a decoded parameter
+    fn from_parameter(parameter: &Parameter) -> Operand {
+        match parameter.mode {
+            ParameterMode::Position => Operand::Position(parameter.value),
+            ParameterMode::Immediate => Operand::Immediate(parameter.value),
+            ParameterMode::Relative => Operand::Relative(parameter.value),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Position(value) => write!(f, "[{}]", value),
+            Operand::Immediate(value) => write!(f, "#{}", value),
+            Operand::Relative(value) if *value < 0 => write!(f, "[rb{}]", value),
+            Operand::Relative(value) => write!(f, "[rb+{}]", value),
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Data(value) => write!(f, "DATA {}", value),
+            Item::Instruction { mnemonic, inputs, output } => {
+                write!(f, "{}", mnemonic)?;
+                let inputs = inputs.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+                if !inputs.is_empty() {
+                    write!(f, " {}", inputs.join(", "))?;
+                }
+                match output {
+                    Some(output) => write!(f, " -> {}", output),
+                    None => Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}: {}", self.address, self.item)
+    }
+}
+
+impl Line {
+    /// Gets the number of words the line covers
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Checks if the line covers no words, which is never the case for a decoded line
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Gets the mnemonic and the output parameter count for an op code
+///
+/// # Examples
+/// ```ignore
+/// assert_eq!(get_mnemonic(OpCodeId::Add), ("ADD", true));
+/// ```
+pub fn get_mnemonic(op_code: OpCodeId) -> (&'static str, bool) {
+    match op_code {
+        OpCodeId::Add                => ("ADD", true),
+        OpCodeId::Multiply           => ("MUL", true),
+        OpCodeId::Input              => ("IN", true),
+        OpCodeId::Output             => ("OUT", false),
+        OpCodeId::JumpIfTrue         => ("JT", false),
+        OpCodeId::JumpIfFalse        => ("JF", false),
+        OpCodeId::LessThan           => ("LT", true),
+        OpCodeId::Equals             => ("EQ", true),
+        OpCodeId::AdjustRelativeBase => ("ARB", false),
+        OpCodeId::Complete           => ("HLT", false),
+    }
+}
+
+/// Disassembles the instruction starting at an address of a memory
+///
+/// Words that do not decode as an instruction, instructions that would run off the end of
+/// the memory, instructions that can not execute because they write to an immediate operand
+/// and words with mode digits for parameters the instruction does not have are returned as a
+/// single `Item::Data` word, so that every listing reassembles to the same words
+pub fn disassemble_at(memory: &Memory, address: usize) -> Line {
+    let words = memory.read_range(address, MAX_INSTRUCTION_LENGTH);
+    let data = Line { address, words: vec![words[0]], item: Item::Data(words[0]) };
+
+    let instruction = match lexer::parse_instruction_type(words[0]) {
+        Some(i) => i,
+        None => return data
+    };
+    let op_code = match FromPrimitive::from_i64(instruction.op_code) {
+        Some(op_code) => op_code,
+        None => return data
+    };
+    let modes = [instruction.c.mode, instruction.b.mode, instruction.a.mode];
+    let op = match Instruction::parse_from_slice(&words) {
+        Some(op) => op,
+        None => return data
+    };
+    let length = op.get_instruction_pointer_offset() as usize;
+    if address + length > memory.len() || words[0] / 10_i64.pow(length as u32 + 1) != 0 {
+        return data;
+    }
+
+    let (mnemonic, has_output) = get_mnemonic(op_code);
+    let mut operands = (1..length)
+        .map(|i| Operand::from_parameter(&Parameter { mode: modes[i - 1], value: words[i] }))
+        .collect::<Vec<Operand>>();
+    let output = match has_output {
+        true => operands.pop(),
+        false => None
+    };
+    if matches!(output, Some(Operand::Immediate(_))) {
+        return data;
+    }
+    Line { address, words: words[..length].to_vec(), item: Item::Instruction { mnemonic, inputs: operands, output } }
+}
+
+/// Disassembles a whole program by sweeping linearly from address zero
+///
+/// # Examples
+/// ```
+/// let lines = intcode::disassembler::disassemble(&[1001, 12, 3, 7, 99]);
+/// assert_eq!(lines[0].to_string(), "0000: ADD [12], #3 -> [7]");
+/// assert_eq!(lines[1].to_string(), "0004: HLT");
+/// ```
+pub fn disassemble(program: &[i64]) -> Vec<Line> {
+    let memory = Memory::new(program.to_vec());
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while address < memory.len() {
+        let line = disassemble_at(&memory, address);
+        address += line.len();
+        lines.push(line);
+    }
+    lines
+}
+
+/// Renders a program as a listing with one instruction per line
+pub fn listing(program: &[i64]) -> String {
+    disassemble(program).iter()
+        .map(|line| line.to_string() + "\n")
+        .collect()
+}
+
+
+#[test]
+fn test_disassemble_operand_modes() {
+    let lines = disassemble(&[1001, 12, 3, 7, 21207, -2, 5, 3, 204, 4, 3, 9, 1106, 0, 4, 99]);
+    let text = lines.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+    assert_eq!(text, vec!["0000: ADD [12], #3 -> [7]",
+                          "0004: LT [rb-2], #5 -> [rb+3]",
+                          "0008: OUT [rb+4]",
+                          "0010: IN -> [9]",
+                          "0012: JF #0, #4",
+                          "0015: HLT"]);
+    assert_eq!(lines[1].words, vec![21207, -2, 5, 3]);
+}
+
+#[test]
+fn test_disassemble_data() {
+    let lines = disassemble(&[99, 12345, 30001, 1, 2, 1101, 1]);
+    let text = lines.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+    assert_eq!(text, vec!["0000: HLT",
+                          "0001: DATA 12345",
+                          "0002: DATA 30001",
+                          "0003: ADD [2], [1101] -> [1]"]);
+
+    // Instructions that would run off the end of the program
+    assert_eq!(listing(&[1101, 1]), "0000: DATA 1101\n0001: DATA 1\n");
+
+    // Instructions that write to an immediate operand fault, so they are data too
+    assert_eq!(listing(&[11009, 0, 99]), "0000: DATA 11009\n0001: DATA 0\n0002: HLT\n");
+    assert_eq!(listing(&[11101, 0, 0, 0, 103, 5]), "0000: DATA 11101\n0001: DATA 0\n0002: DATA 0\n0003: DATA 0\n0004: DATA 103\n0005: DATA 5\n");
+}
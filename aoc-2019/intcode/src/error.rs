@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Errors raised while loading or running an intcode program
+///
+/// ## Note
+/// Errors raised while executing carry the instruction pointer and raw instruction word of the
+/// faulting instruction. Lower level helpers that do not know where they were called from leave
+/// these as zero and the machine fills them in with `at`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntcodeError {
+    /// The op code of an instruction word is not part of the instruction set
+    UnknownOpcode { ip: usize, word: i64 },
+    /// A parameter mode digit of an instruction word is not a known mode
+    InvalidParameterMode { ip: usize, word: i64 },
+    /// An instruction tried to write through an immediate mode parameter
+    WriteToImmediate { ip: usize, word: i64 },
+    /// An instruction tried to write past the highest address the memory supports
+    AddressOutOfRange { ip: usize, word: i64, address: i64 },
+    /// An instruction tried to access a negative address
+    NegativeAddress { ip: usize, word: i64, address: i64 },
+    /// An arithmetic result, relative address or relative base does not fit in 64 bits
+    Overflow { ip: usize, word: i64 },
+    /// A token of a program listing is not an integer, line and column are one based
+    ParseError { line: usize, column: usize, token: String },
+    /// An input instruction ran when no more input was available
+    InputExhausted { ip: usize, word: i64 },
+    /// A run executed its maximum number of instructions before the program halted
+    StepLimit { ip: usize, word: i64, steps: u64 },
+    /// A program file could not be read
+    Io(String),
+}
+
+impl IntcodeError {
+    /// Fills in the location of the faulting instruction for execution errors
+    ///
+    /// # Arguments
+    /// * `ip`   - Address of the faulting instruction
+    /// * `word` - Raw instruction word at that address
+    pub fn at(self, ip: usize, word: i64) -> IntcodeError {
+        match self {
+            IntcodeError::UnknownOpcode { .. } => IntcodeError::UnknownOpcode { ip, word },
+            IntcodeError::InvalidParameterMode { .. } => IntcodeError::InvalidParameterMode { ip, word },
+            IntcodeError::WriteToImmediate { .. } => IntcodeError::WriteToImmediate { ip, word },
+            IntcodeError::AddressOutOfRange { address, .. } => IntcodeError::AddressOutOfRange { ip, word, address },
+            IntcodeError::NegativeAddress { address, .. } => IntcodeError::NegativeAddress { ip, word, address },
+            IntcodeError::Overflow { .. } => IntcodeError::Overflow { ip, word },
+            IntcodeError::InputExhausted { .. } => IntcodeError::InputExhausted { ip, word },
+            IntcodeError::StepLimit { steps, .. } => IntcodeError::StepLimit { ip, word, steps },
+            IntcodeError::ParseError { .. } | IntcodeError::Io(_) => self,
+        }
+    }
+
+    /// Gets the address of the faulting instruction for execution errors
+    pub fn instruction_pointer(&self) -> Option<usize> {
+        match self {
+            IntcodeError::UnknownOpcode { ip, .. } |
+            IntcodeError::InvalidParameterMode { ip, .. } |
+            IntcodeError::WriteToImmediate { ip, .. } |
+            IntcodeError::AddressOutOfRange { ip, .. } |
+            IntcodeError::NegativeAddress { ip, .. } |
+            IntcodeError::Overflow { ip, .. } |
+            IntcodeError::InputExhausted { ip, .. } |
+            IntcodeError::StepLimit { ip, .. } => Some(*ip),
+            IntcodeError::ParseError { .. } | IntcodeError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode { ip, word } =>
+                write!(f, "unknown op code in instruction {} at address {}", word, ip),
+            IntcodeError::InvalidParameterMode { ip, word } =>
+                write!(f, "invalid parameter mode in instruction {} at address {}", word, ip),
+            IntcodeError::WriteToImmediate { ip, word } =>
+                write!(f, "instruction {} at address {} writes to an immediate parameter", word, ip),
+            IntcodeError::AddressOutOfRange { ip, word, address } =>
+                write!(f, "instruction {} at address {} accesses out of range address {}", word, ip, address),
+            IntcodeError::NegativeAddress { ip, word, address } =>
+                write!(f, "instruction {} at address {} accesses negative address {}", word, ip, address),
+            IntcodeError::Overflow { ip, word } =>
+                write!(f, "instruction {} at address {} overflows 64 bits", word, ip),
+            IntcodeError::ParseError { line, column, token } =>
+                write!(f, "could not parse {:?} as an integer at line {}, column {}", token, line, column),
+            IntcodeError::InputExhausted { ip, word } =>
+                write!(f, "instruction {} at address {} ran out of input", word, ip),
+            IntcodeError::StepLimit { ip, word, steps } =>
+                write!(f, "reached the limit of {} steps at instruction {} at address {}", steps, word, ip),
+            IntcodeError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for IntcodeError {}
+
+impl From<std::io::Error> for IntcodeError {
+    fn from(error: std::io::Error) -> Self {
+        IntcodeError::Io(error.to_string())
+    }
+}
+
+
+#[test]
+fn test_at_fills_in_location() {
+    let error = IntcodeError::NegativeAddress { ip: 0, word: 0, address: -4 }.at(12, 1001);
+    assert_eq!(error, IntcodeError::NegativeAddress { ip: 12, word: 1001, address: -4 });
+    assert_eq!(error.instruction_pointer(), Some(12));
+
+    let error = IntcodeError::ParseError { line: 1, column: 3, token: String::from("x") };
+    assert_eq!(error.clone().at(12, 1001), error);
+    assert_eq!(error.instruction_pointer(), None);
+}
@@ -0,0 +1,379 @@
+pub mod arcade;
+pub mod robot;
+
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A position on a grid, with `y` growing downwards like the rows of a screen
+pub type Point = (i64, i64);
+
+/// An error raised by a grid or one of the drivers built on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    /// The program driving the grid faulted
+    Intcode(IntcodeError),
+    /// The program output a value that the driver does not understand
+    UnexpectedOutput { value: i64, expected: String },
+    /// The grid covers too many tiles to be drawn, corners are inclusive
+    TooLarge { min: Point, max: Point },
+    /// An image could not be encoded or written
+    Io(String),
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Intcode(e) => write!(f, "{}", e),
+            GridError::UnexpectedOutput { value, expected } => write!(f, "program output {} where {} was expected", value, expected),
+            GridError::TooLarge { min, max } => write!(f, "grid from {:?} to {:?} is too large to draw", min, max),
+            GridError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+impl From<IntcodeError> for GridError {
+    fn from(error: IntcodeError) -> Self {
+        GridError::Intcode(error)
+    }
+}
+
+impl From<std::io::Error> for GridError {
+    fn from(error: std::io::Error) -> Self {
+        GridError::Io(error.to_string())
+    }
+}
+
+/// Largest number of characters or pixels a grid is drawn with
+///
+/// The tiles come from program output, so tiles far apart are refused instead of allocating a
+/// canvas that covers them
+pub const MAX_CANVAS_SIZE: usize = 1 << 26;
+
+/// The smallest rectangle that contains every tile of a grid, with inclusive corners
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    /// Gets the number of columns the bounds cover, or `None` if it does not fit in a `usize`
+    pub fn width(&self) -> Option<usize> {
+        span(self.min.0, self.max.0)
+    }
+
+    /// Gets the number of rows the bounds cover, or `None` if it does not fit in a `usize`
+    pub fn height(&self) -> Option<usize> {
+        span(self.min.1, self.max.1)
+    }
+
+    /// Gets the width and height of a canvas drawing each tile as a square of `scale` cells
+    ///
+    /// Returns `GridError::TooLarge` if the canvas has more than `MAX_CANVAS_SIZE` cells
+    pub fn canvas(&self, scale: usize) -> Result<(usize, usize), GridError> {
+        let error = || GridError::TooLarge { min: self.min, max: self.max };
+        let width = self.width().and_then(|w| w.checked_mul(scale)).ok_or_else(error)?;
+        let height = self.height().and_then(|h| h.checked_mul(scale)).ok_or_else(error)?;
+        match width.checked_mul(height) {
+            Some(size) if size <= MAX_CANVAS_SIZE => Ok((width, height)),
+            _ => Err(error())
+        }
+    }
+}
+
+/// Helper function to count the values of an inclusive range without overflowing
+fn span(min: i64, max: i64) -> Option<usize> {
+    usize::try_from(max.abs_diff(min)).ok()?.checked_add(1)
+}
+
+/// A sparse grid of tiles, such as the panels painted by a robot or the screen of a game
+///
+/// # Examples
+/// ```
+/// use intcode::grid::{Grid, Legend};
+/// // Two tiles drawn as (x, y, tile) output triples
+/// let grid = Grid::from_triples(&[0, 0, 1, 2, 1, 2]);
+/// let legend = Legend::default().tile(1, '#', "wall", [255, 255, 255]).tile(2, 'o', "ball", [255, 0, 0]);
+/// assert_eq!(grid.render(&legend), Ok(String::from("#  \n  o\n")));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grid {
+    tiles: HashMap<Point, i64>,
+    bounds: Option<Bounds>,
+}
+
+impl Grid {
+    /// Creates an empty grid
+    pub fn new() -> Grid {
+        Grid::default()
+    }
+
+    /// Creates a grid from output values grouped as `x`, `y` and tile
+    ///
+    /// ## Note
+    /// A trailing incomplete triple is ignored
+    pub fn from_triples(values: &[i64]) -> Grid {
+        let mut grid = Grid::new();
+        for triple in values.chunks_exact(3) {
+            grid.set((triple[0], triple[1]), triple[2]);
+        }
+        grid
+    }
+
+    /// Sets the tile at a position, growing the bounds to include it
+    ///
+    /// Returns the tile that was there before
+    pub fn set(&mut self, position: Point, tile: i64) -> Option<i64> {
+        self.bounds = Some(match self.bounds {
+            Some(Bounds { min, max }) => Bounds {
+                min: (min.0.min(position.0), min.1.min(position.1)),
+                max: (max.0.max(position.0), max.1.max(position.1)),
+            },
+            None => Bounds { min: position, max: position }
+        });
+        self.tiles.insert(position, tile)
+    }
+
+    /// Gets the tile at a position if one was set
+    pub fn get(&self, position: Point) -> Option<i64> {
+        self.tiles.get(&position).copied()
+    }
+
+    /// Gets the bounds of every tile that was set, or `None` for an empty grid
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    /// Gets the number of positions that have a tile
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Checks if no tile has been set
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Counts the positions holding a tile
+    pub fn count(&self, tile: i64) -> usize {
+        self.tiles.values().filter(|t| **t == tile).count()
+    }
+
+    /// Finds a position holding a tile
+    pub fn find(&self, tile: i64) -> Option<Point> {
+        self.tiles.iter().find(|(_, t)| **t == tile).map(|(position, _)| *position)
+    }
+
+    /// Iterates over every position and its tile in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Point, i64)> + '_ {
+        self.tiles.iter().map(|(position, tile)| (*position, *tile))
+    }
+
+    /// Renders the bounds of the grid as text, one line per row
+    ///
+    /// Positions without a tile are drawn as spaces and tiles missing from the legend as `?`.
+    /// Returns `GridError::TooLarge` if the text would have more than `MAX_CANVAS_SIZE` tiles
+    pub fn render(&self, legend: &Legend) -> Result<String, GridError> {
+        let bounds = match self.bounds {
+            Some(b) => b,
+            None => return Ok(String::new())
+        };
+        let (width, height) = bounds.canvas(1)?;
+        let mut text = String::with_capacity((width + 1) * height);
+        for y in bounds.min.1..=bounds.max.1 {
+            for x in bounds.min.0..=bounds.max.0 {
+                text.push(match self.get((x, y)) {
+                    Some(tile) => legend.symbol(tile),
+                    None => ' '
+                });
+            }
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    /// Encodes the bounds of the grid as an RGB PNG image
+    ///
+    /// # Arguments
+    /// * `writer` - Destination of the image
+    /// * `legend` - Colours of the tiles, positions without a tile are black
+    /// * `scale`  - Width and height in pixels of each tile
+    ///
+    /// Returns `GridError::TooLarge` if the image would have more than `MAX_CANVAS_SIZE` pixels
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, writer: impl std::io::Write, legend: &Legend, scale: u32) -> Result<(), GridError> {
+        let bounds = self.bounds.unwrap_or(Bounds { min: (0, 0), max: (0, 0) });
+        let scale = scale.max(1) as usize;
+        let (width, height) = bounds.canvas(scale)?;
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let position = (bounds.min.0 + (x / scale) as i64, bounds.min.1 + (y / scale) as i64);
+                pixels.extend_from_slice(&match self.get(position) {
+                    Some(tile) => legend.color(tile),
+                    None => [0, 0, 0]
+                });
+            }
+        }
+
+        let error = |e: png::EncodingError| GridError::Io(e.to_string());
+        let too_large = |_| GridError::TooLarge { min: bounds.min, max: bounds.max };
+        let mut encoder = png::Encoder::new(writer, u32::try_from(width).map_err(too_large)?, u32::try_from(height).map_err(too_large)?);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(error)?;
+        writer.write_image_data(&pixels).map_err(error)?;
+        writer.finish().map_err(error)
+    }
+
+    /// Saves the grid as a PNG image, see `write_png`
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, filename: impl AsRef<std::path::Path>, legend: &Legend, scale: u32) -> Result<(), GridError> {
+        let file = std::io::BufWriter::new(std::fs::File::create(filename)?);
+        self.write_png(file, legend, scale)
+    }
+}
+
+/// How each kind of tile is drawn
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Legend {
+    tiles: BTreeMap<i64, LegendEntry>,
+}
+
+/// How a single kind of tile is drawn
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LegendEntry {
+    symbol: char,
+    name: String,
+    color: [u8; 3],
+}
+
+impl Legend {
+    /// Adds a kind of tile
+    ///
+    /// # Arguments
+    /// * `tile`   - The tile value
+    /// * `symbol` - Character used when rendering as text
+    /// * `name`   - Description shown in the key
+    /// * `color`  - RGB colour used in images
+    pub fn tile(mut self, tile: i64, symbol: char, name: &str, color: [u8; 3]) -> Legend {
+        self.tiles.insert(tile, LegendEntry { symbol, name: name.to_string(), color });
+        self
+    }
+
+    /// Gets the character a tile is rendered as
+    pub fn symbol(&self, tile: i64) -> char {
+        self.tiles.get(&tile).map_or('?', |entry| entry.symbol)
+    }
+
+    /// Gets the colour a tile is drawn in, magenta for tiles missing from the legend
+    pub fn color(&self, tile: i64) -> [u8; 3] {
+        self.tiles.get(&tile).map_or([255, 0, 255], |entry| entry.color)
+    }
+}
+
+/// Renders the key of the legend, one tile per line
+impl fmt::Display for Legend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (tile, entry) in &self.tiles {
+            writeln!(f, "'{}' {} ({})", entry.symbol, entry.name, tile)?;
+        }
+        Ok(())
+    }
+}
+
+/// I/O that draws every output triple of `x`, `y` and tile onto a grid
+///
+/// # Examples
+/// ```
+/// let mut program = vec![104, 3, 104, -1, 104, 7, 99];
+/// let mut io = intcode::grid::GridIo::default();
+/// intcode::run_interpreter(&mut program, &mut io).unwrap();
+/// assert_eq!(io.grid().get((3, -1)), Some(7));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GridIo {
+    grid: Grid,
+    pending: Vec<i64>,
+}
+
+impl GridIo {
+    /// Gets the grid drawn so far
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Consumes the I/O and returns the grid
+    pub fn into_grid(self) -> Grid {
+        self.grid
+    }
+}
+
+impl IntcodeIo for GridIo {
+    /// Programs that only draw take no input
+    fn read(&mut self) -> Option<i64> {
+        None
+    }
+
+    fn write(&mut self, value: i64) {
+        self.pending.push(value);
+        if let [x, y, tile] = self.pending[..] {
+            self.grid.set((x, y), tile);
+            self.pending.clear();
+        }
+    }
+}
+
+
+#[test]
+fn test_bounds_and_render() {
+    let mut grid = Grid::new();
+    assert_eq!(grid.bounds(), None);
+    assert_eq!(grid.render(&Legend::default()), Ok(String::new()));
+
+    grid.set((2, 1), 1);
+    grid.set((-1, 3), 2);
+    assert_eq!(grid.set((2, 1), 5), Some(1));
+    assert_eq!(grid.bounds(), Some(Bounds { min: (-1, 1), max: (2, 3) }));
+    assert_eq!((grid.bounds().unwrap().width(), grid.bounds().unwrap().height()), (Some(4), Some(3)));
+    assert_eq!((grid.len(), grid.count(2), grid.find(5)), (2, 1, Some((2, 1))));
+
+    let legend = Legend::default().tile(2, '#', "wall", [255, 255, 255]);
+    assert_eq!(grid.render(&legend), Ok(String::from("   ?\n    \n#   \n")));
+    assert_eq!(legend.to_string(), "'#' wall (2)\n");
+}
+
+#[test]
+fn test_far_apart_tiles() {
+    let grid = Grid::from_triples(&[i64::MIN, 0, 1, i64::MAX, 0, 1]);
+    assert_eq!(grid.bounds().unwrap().width(), None);
+    assert_eq!(grid.render(&Legend::default()), Err(GridError::TooLarge { min: (i64::MIN, 0), max: (i64::MAX, 0) }));
+
+    let grid = Grid::from_triples(&[0, 0, 1, 1 << 20, 1 << 20, 1]);
+    assert_eq!(grid.bounds().unwrap().canvas(1), Err(GridError::TooLarge { min: (0, 0), max: (1 << 20, 1 << 20) }));
+    assert!(grid.render(&Legend::default()).is_err());
+    assert_eq!(Grid::from_triples(&[0, 0, 1, 2047, 2047, 1]).bounds().unwrap().canvas(4), Ok((8192, 8192)));
+}
+
+#[test]
+#[cfg(feature = "png")]
+fn test_write_png() {
+    let grid = Grid::from_triples(&[0, 0, 1, 1, 1, 2]);
+    let legend = Legend::default().tile(1, '#', "wall", [10, 20, 30]);
+    let mut bytes = Vec::new();
+    grid.write_png(&mut bytes, &legend, 2).unwrap();
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(bytes)).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height, info.color_type), (4, 4, png::ColorType::Rgb));
+    assert_eq!(&pixels[..12], &[10, 20, 30, 10, 20, 30, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&pixels[pixels.len() - 3..], &[255, 0, 255]);
+
+    let grid = Grid::from_triples(&[0, 0, 1, 2000, 2000, 1]);
+    assert_eq!(grid.write_png(Vec::new(), &legend, 8), Err(GridError::TooLarge { min: (0, 0), max: (2000, 2000) }));
+}
@@ -0,0 +1,132 @@
+use crate::grid::{Grid, GridError, Legend, Point};
+use crate::io::IntcodeIo;
+
+/// An empty tile
+pub const EMPTY: i64 = 0;
+
+/// An indestructible wall tile
+pub const WALL: i64 = 1;
+
+/// A block tile, broken by the ball
+pub const BLOCK: i64 = 2;
+
+/// The horizontal paddle tile
+pub const PADDLE: i64 = 3;
+
+/// The ball tile
+pub const BALL: i64 = 4;
+
+/// An arcade cabinet driven by an intcode game
+///
+/// Outputs come in triples of `x`, `y` and tile, except for `-1, 0, score` which updates the
+/// score display. Each input is the joystick position, which the cabinet sets itself so that the
+/// paddle follows the ball
+///
+/// # Examples
+/// ```
+/// use intcode::grid::arcade::{Arcade, BLOCK};
+/// let mut arcade = Arcade::default();
+/// arcade.run(vec![104, 1, 104, 2, 104, 2, 104, -1, 104, 0, 104, 30, 99], false).unwrap();
+/// assert_eq!((arcade.blocks(), arcade.score()), (1, 30));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Arcade {
+    screen: Grid,
+    pending: Vec<i64>,
+    score: i64,
+    ball: Option<Point>,
+    paddle: Option<Point>,
+}
+
+impl Arcade {
+    /// Runs a game until it halts
+    ///
+    /// # Arguments
+    /// * `program`   - The game
+    /// * `free_play` - Sets address `0` to `2` so that the game can be played without quarters
+    pub fn run(&mut self, mut program: Vec<i64>, free_play: bool) -> Result<(), GridError> {
+        if free_play && !program.is_empty() {
+            program[0] = 2;
+        }
+        crate::run_interpreter(&mut program, self).map(|_| ()).map_err(GridError::from)
+    }
+
+    /// Gets the tiles drawn so far
+    pub fn screen(&self) -> &Grid {
+        &self.screen
+    }
+
+    /// Gets the last score displayed
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    /// Gets the number of block tiles on the screen
+    pub fn blocks(&self) -> usize {
+        self.screen.count(BLOCK)
+    }
+
+    /// Gets the legend of the tiles
+    pub fn legend() -> Legend {
+        Legend::default()
+            .tile(EMPTY, ' ', "empty", [0, 0, 0])
+            .tile(WALL, '#', "wall", [128, 128, 128])
+            .tile(BLOCK, '=', "block", [0, 160, 255])
+            .tile(PADDLE, '_', "paddle", [255, 255, 255])
+            .tile(BALL, 'o', "ball", [255, 200, 0])
+    }
+}
+
+impl IntcodeIo for Arcade {
+    /// Tilts the joystick towards the ball
+    fn read(&mut self) -> Option<i64> {
+        match (self.ball, self.paddle) {
+            (Some(ball), Some(paddle)) => Some((ball.0 - paddle.0).signum()),
+            _ => Some(0)
+        }
+    }
+
+    fn write(&mut self, value: i64) {
+        self.pending.push(value);
+        match self.pending[..] {
+            [-1, 0, score] => self.score = score,
+            [x, y, tile] => {
+                match tile {
+                    BALL => self.ball = Some((x, y)),
+                    PADDLE => self.paddle = Some((x, y)),
+                    _ => {}
+                }
+                self.screen.set((x, y), tile);
+            },
+            _ => return
+        }
+        self.pending.clear();
+    }
+}
+
+
+#[test]
+fn test_arcade() {
+    let program = vec![
+        // Doubles address 0 into the starting score, so free play shows up in the score
+        1, 0, 0, 200,
+        104, 0, 104, 0, 104, 1,
+        104, 2, 104, 0, 104, 2,
+        104, 1, 104, 2, 104, 3,
+        104, 3, 104, 1, 104, 4,
+        // Multiplies the score by the joystick position
+        3, 201,
+        2, 200, 201, 202,
+        104, -1, 104, 0, 4, 202,
+        99,
+    ];
+
+    let mut arcade = Arcade::default();
+    arcade.run(program.clone(), false).unwrap();
+    assert_eq!((arcade.blocks(), arcade.score()), (1, 2));
+    assert_eq!(arcade.screen().render(&Arcade::legend()), Ok(String::from("# = \n   o\n _  \n")));
+
+    let mut arcade = Arcade::default();
+    arcade.run(program, true).unwrap();
+    assert_eq!(arcade.score(), 4);
+}
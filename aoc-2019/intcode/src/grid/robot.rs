@@ -0,0 +1,145 @@
+use crate::grid::{Grid, GridError, Legend, Point};
+use crate::io::IntcodeIo;
+use std::collections::HashSet;
+
+/// Colour of a black panel
+pub const BLACK: i64 = 0;
+
+/// Colour of a white panel
+pub const WHITE: i64 = 1;
+
+/// A hull painting robot driven by an intcode program
+///
+/// Each input is the colour of the panel under the robot. Outputs come in pairs: the colour to
+/// paint the panel, then the way to turn, `0` for left and `1` for right, before moving forward
+/// one panel. Panels that were never painted are black. Any other turn stops the robot, it
+/// gives the program no more input
+///
+/// # Examples
+/// ```
+/// use intcode::grid::robot::{PaintingRobot, WHITE};
+/// // Paints the starting panel white, turns right and halts
+/// let mut robot = PaintingRobot::new(WHITE);
+/// robot.run(vec![3, 100, 4, 100, 104, 1, 99]).unwrap();
+/// assert_eq!((robot.painted(), robot.position()), (1, (1, 0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PaintingRobot {
+    grid: Grid,
+    painted: HashSet<Point>,
+    position: Point,
+    direction: Point,
+    turning: bool,
+    invalid_turn: Option<i64>,
+}
+
+impl PaintingRobot {
+    /// Creates a robot facing up on a starting panel of the given colour
+    pub fn new(start: i64) -> PaintingRobot {
+        let mut grid = Grid::new();
+        grid.set((0, 0), start);
+        PaintingRobot { grid, painted: HashSet::new(), position: (0, 0), direction: (0, -1), turning: false, invalid_turn: None }
+    }
+
+    /// Runs a painting program until it halts
+    ///
+    /// Returns `GridError::UnexpectedOutput` if the program outputs a turn other than `0` or `1`
+    pub fn run(&mut self, mut program: Vec<i64>) -> Result<(), GridError> {
+        let result = crate::run_interpreter(&mut program, self);
+        match self.invalid_turn {
+            Some(value) => Err(GridError::UnexpectedOutput { value, expected: String::from("a turn of 0 or 1") }),
+            None => result.map(|_| ()).map_err(GridError::from)
+        }
+    }
+
+    /// Gets the panels, including the starting panel
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Gets the number of panels painted at least once
+    pub fn painted(&self) -> usize {
+        self.painted.len()
+    }
+
+    /// Gets the panel the robot is on
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    /// Gets the turn that stopped the robot, if the program output one that is not `0` or `1`
+    pub fn invalid_turn(&self) -> Option<i64> {
+        self.invalid_turn
+    }
+
+    /// Gets the legend of the panel colours
+    pub fn legend() -> Legend {
+        Legend::default()
+            .tile(BLACK, '.', "black", [0, 0, 0])
+            .tile(WHITE, '#', "white", [255, 255, 255])
+    }
+}
+
+impl IntcodeIo for PaintingRobot {
+    fn read(&mut self) -> Option<i64> {
+        match self.invalid_turn {
+            Some(_) => None,
+            None => Some(self.grid.get(self.position).unwrap_or(BLACK))
+        }
+    }
+
+    fn write(&mut self, value: i64) {
+        if self.invalid_turn.is_some() {
+            return;
+        }
+        match self.turning {
+            true => {
+                let (x, y) = self.direction;
+                self.direction = match value {
+                    0 => (y, -x),
+                    1 => (-y, x),
+                    _ => {
+                        self.invalid_turn = Some(value);
+                        return;
+                    }
+                };
+                self.position = (self.position.0 + self.direction.0, self.position.1 + self.direction.1);
+            },
+            false => {
+                self.grid.set(self.position, value);
+                self.painted.insert(self.position);
+            }
+        }
+        self.turning = !self.turning;
+    }
+}
+
+
+#[test]
+fn test_painting_robot() {
+    // Replays the example moves of the day 11 puzzle, reading the panel before each pair
+    let moves = [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+    let mut program = Vec::new();
+    let mut reads = Vec::new();
+    for (i, (color, turn)) in moves.iter().enumerate() {
+        program.extend_from_slice(&[3, 200 + i as i64, 104, *color, 104, *turn]);
+        reads.push(200 + i);
+    }
+    program.push(99);
+
+    let mut robot = PaintingRobot::new(BLACK);
+    let mut memory = program;
+    crate::run_interpreter(&mut memory, &mut robot).unwrap();
+    assert_eq!(reads.iter().map(|a| memory[*a]).collect::<Vec<_>>(), vec![0, 0, 0, 0, 1, 0, 0]);
+    assert_eq!((robot.painted(), robot.position()), (6, (0, -1)));
+    assert_eq!(robot.grid().render(&PaintingRobot::legend()), Ok(String::from("  #\n..#\n## \n")));
+}
+
+#[test]
+fn test_invalid_turn_stops_robot() {
+    // Paints white and turns 2, then would read the panel and paint it again
+    let mut robot = PaintingRobot::new(BLACK);
+    let result = robot.run(vec![104, 1, 104, 2, 3, 100, 104, 1, 104, 0, 99]);
+    assert_eq!(result, Err(GridError::UnexpectedOutput { value: 2, expected: String::from("a turn of 0 or 1") }));
+    assert_eq!((robot.invalid_turn(), robot.painted(), robot.position()), (Some(2), 1, (0, 0)));
+}
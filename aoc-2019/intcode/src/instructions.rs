@@ -8,9 +8,15 @@ pub mod jump_if_true;
 pub mod jump_if_false;
 pub mod less_than;
 pub mod equals;
+pub mod adjust_relative_base;
 
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 use num_traits::FromPrimitive;
+use std::fmt;
+use std::sync::Arc;
 
 use crate::instructions::{
     op_code::{OpCode, OpCodeId},
@@ -23,21 +29,160 @@ use crate::instructions::{
     jump_if_false::JumpIfFalse,
     less_than::LessThan,
     equals::Equals,
+    adjust_relative_base::AdjustRelativeBase,
 };
 
+/// Number of words that make up the longest instruction
+pub const MAX_INSTRUCTION_LENGTH: usize = 4;
+
 /// Creates a dynamic trait object from an instruction type and the current program context
+///
+/// ## Note
+/// Allocates on every call, the interpreter decodes into an `Instruction` instead
 pub fn parse_from_slice(instruction: lexer::InstructionType, program_context: &[i64]) -> Option<Box<dyn OpCode>> {
     let op: Box<dyn OpCode> = match FromPrimitive::from_i64(instruction.op_code) {
-        Some(OpCodeId::Add)         => Box::new(Add::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::Multiply)    => Box::new(Multiply::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::Input)       => Box::new(Input::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::Output)      => Box::new(Output::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::JumpIfTrue)  => Box::new(JumpIfTrue::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::JumpIfFalse) => Box::new(JumpIfFalse::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::LessThan)    => Box::new(LessThan::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::Equals)      => Box::new(Equals::parse_from_slice(program_context).unwrap()),
-        Some(OpCodeId::Complete)    => Box::new(Complete::parse_from_slice(program_context).unwrap()),
+        Some(OpCodeId::Add)         => Box::new(Add::parse_from_slice(program_context)?),
+        Some(OpCodeId::Multiply)    => Box::new(Multiply::parse_from_slice(program_context)?),
+        Some(OpCodeId::Input)       => Box::new(Input::parse_from_slice(program_context)?),
+        Some(OpCodeId::Output)      => Box::new(Output::parse_from_slice(program_context)?),
+        Some(OpCodeId::JumpIfTrue)  => Box::new(JumpIfTrue::parse_from_slice(program_context)?),
+        Some(OpCodeId::JumpIfFalse) => Box::new(JumpIfFalse::parse_from_slice(program_context)?),
+        Some(OpCodeId::LessThan)    => Box::new(LessThan::parse_from_slice(program_context)?),
+        Some(OpCodeId::Equals)      => Box::new(Equals::parse_from_slice(program_context)?),
+        Some(OpCodeId::AdjustRelativeBase) => Box::new(AdjustRelativeBase::parse_from_slice(program_context)?),
+        Some(OpCodeId::Complete)    => Box::new(Complete::parse_from_slice(program_context)?),
         None => return None
     };
     Some(op)    
-}
\ No newline at end of file
+}
+
+/// A decoded instruction that is dispatched with a match instead of a trait object
+///
+/// Unlike `parse_from_slice` this does not allocate for built-in instructions, so decoded
+/// instructions can be cached and reused every time the instruction pointer returns to the
+/// same address. Operations added through an `InstructionSet` are shared behind `Custom`
+#[derive(Clone)]
+pub enum Instruction {
+    Add(Add),
+    Multiply(Multiply),
+    Input(Input),
+    Output(Output),
+    JumpIfTrue(JumpIfTrue),
+    JumpIfFalse(JumpIfFalse),
+    LessThan(LessThan),
+    Equals(Equals),
+    AdjustRelativeBase(AdjustRelativeBase),
+    Complete(Complete),
+    Custom(Arc<dyn OpCode + Send + Sync>),
+}
+
+/// Helper macro to forward a method call to the operation inside an instruction
+macro_rules! dispatch {
+    ($instruction:expr, $op:ident => $call:expr) => {
+        match $instruction {
+            Instruction::Add($op)                => $call,
+            Instruction::Multiply($op)           => $call,
+            Instruction::Input($op)              => $call,
+            Instruction::Output($op)             => $call,
+            Instruction::JumpIfTrue($op)         => $call,
+            Instruction::JumpIfFalse($op)        => $call,
+            Instruction::LessThan($op)           => $call,
+            Instruction::Equals($op)             => $call,
+            Instruction::AdjustRelativeBase($op) => $call,
+            Instruction::Complete($op)           => $call,
+            Instruction::Custom($op)             => $call,
+        }
+    };
+}
+
+impl OpCode for Instruction {
+    /// Decodes the instruction at the start of a slice of a program
+    ///
+    /// # Examples
+    /// ```
+    /// use intcode::instructions::{Instruction, op_code::OpCode};
+    /// let instruction = Instruction::parse_from_slice(&[1002, 4, 3, 4]).unwrap();
+    /// assert_eq!(instruction.get_op_code(), 2);
+    /// ```
+    fn parse_from_slice(program: &[i64]) -> Option<Instruction> {
+        let op_code = lexer::parse_instruction_type(*program.first()?)?.op_code;
+        let instruction = match FromPrimitive::from_i64(op_code)? {
+            OpCodeId::Add                => Instruction::Add(Add::parse_from_slice(program)?),
+            OpCodeId::Multiply           => Instruction::Multiply(Multiply::parse_from_slice(program)?),
+            OpCodeId::Input              => Instruction::Input(Input::parse_from_slice(program)?),
+            OpCodeId::Output             => Instruction::Output(Output::parse_from_slice(program)?),
+            OpCodeId::JumpIfTrue         => Instruction::JumpIfTrue(JumpIfTrue::parse_from_slice(program)?),
+            OpCodeId::JumpIfFalse        => Instruction::JumpIfFalse(JumpIfFalse::parse_from_slice(program)?),
+            OpCodeId::LessThan           => Instruction::LessThan(LessThan::parse_from_slice(program)?),
+            OpCodeId::Equals             => Instruction::Equals(Equals::parse_from_slice(program)?),
+            OpCodeId::AdjustRelativeBase => Instruction::AdjustRelativeBase(AdjustRelativeBase::parse_from_slice(program)?),
+            OpCodeId::Complete           => Instruction::Complete(Complete::parse_from_slice(program)?),
+        };
+        Some(instruction)
+    }
+
+    fn get_instruction_pointer_offset(&self) -> i64 {
+        dispatch!(self, op => op.get_instruction_pointer_offset())
+    }
+
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        dispatch!(self, op => op.apply(program, instruction_pointer, relative_base, io))
+    }
+
+    fn get_op_code(&self) -> i64 {
+        dispatch!(self, op => op.get_op_code())
+    }
+
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        dispatch!(self, op => op.get_output())
+    }
+}
+
+impl fmt::Debug for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Add(op)                => f.debug_tuple("Add").field(op).finish(),
+            Instruction::Multiply(op)           => f.debug_tuple("Multiply").field(op).finish(),
+            Instruction::Input(op)              => f.debug_tuple("Input").field(op).finish(),
+            Instruction::Output(op)             => f.debug_tuple("Output").field(op).finish(),
+            Instruction::JumpIfTrue(op)         => f.debug_tuple("JumpIfTrue").field(op).finish(),
+            Instruction::JumpIfFalse(op)        => f.debug_tuple("JumpIfFalse").field(op).finish(),
+            Instruction::LessThan(op)           => f.debug_tuple("LessThan").field(op).finish(),
+            Instruction::Equals(op)             => f.debug_tuple("Equals").field(op).finish(),
+            Instruction::AdjustRelativeBase(op) => f.debug_tuple("AdjustRelativeBase").field(op).finish(),
+            Instruction::Complete(op)           => f.debug_tuple("Complete").field(op).finish(),
+            Instruction::Custom(op)             => f.debug_tuple("Custom").field(&op.get_op_code()).finish(),
+        }
+    }
+}
+
+/// Custom operations are only equal to themselves since they can not be compared
+impl PartialEq for Instruction {
+    fn eq(&self, other: &Instruction) -> bool {
+        match (self, other) {
+            (Instruction::Add(a), Instruction::Add(b)) => a == b,
+            (Instruction::Multiply(a), Instruction::Multiply(b)) => a == b,
+            (Instruction::Input(a), Instruction::Input(b)) => a == b,
+            (Instruction::Output(a), Instruction::Output(b)) => a == b,
+            (Instruction::JumpIfTrue(a), Instruction::JumpIfTrue(b)) => a == b,
+            (Instruction::JumpIfFalse(a), Instruction::JumpIfFalse(b)) => a == b,
+            (Instruction::LessThan(a), Instruction::LessThan(b)) => a == b,
+            (Instruction::Equals(a), Instruction::Equals(b)) => a == b,
+            (Instruction::AdjustRelativeBase(a), Instruction::AdjustRelativeBase(b)) => a == b,
+            (Instruction::Complete(a), Instruction::Complete(b)) => a == b,
+            (Instruction::Custom(a), Instruction::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false
+        }
+    }
+}
+
+
+#[test]
+fn test_decode_instruction() {
+    assert_eq!(Instruction::parse_from_slice(&[1101, 2, 3, 4]).map(|i| i.get_op_code()), Some(1));
+    assert_eq!(Instruction::parse_from_slice(&[99, 0, 0, 0]).map(|i| i.get_instruction_pointer_offset()), Some(1));
+    assert_eq!(Instruction::parse_from_slice(&[21107, 1, 2, 5]).and_then(|i| i.get_output().copied()),
+               Some(lexer::Parameter { mode: crate::parameters::ParameterMode::Relative, value: 5 }));
+    assert_eq!(Instruction::parse_from_slice(&[42, 0, 0, 0]), None);
+    assert_eq!(Instruction::parse_from_slice(&[]), None);
+}
@@ -1,13 +1,18 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 #[allow(unused_imports)]
 use crate::parameters::ParameterMode;
+#[allow(unused_imports)]
+use crate::io::QueueIo;
 
 
 const OP_CODE_ID: i64 = 1;
 const INSTRUCTION_POINTER_OFFSET: i64 = 4;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct Add {
     arg1: lexer::Parameter,
     arg2: lexer::Parameter,
@@ -34,10 +39,12 @@ impl OpCode for Add {
     }
 
     /// Applies an addition operation
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        let result = lexer::get_parameter_value(&self.arg1, &program) + lexer::get_parameter_value(&self.arg2, &program);
-        program[self.output.value as usize] = result;
-        instruction_pointer + INSTRUCTION_POINTER_OFFSET
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let result = lexer::get_parameter_value(&self.arg1, program, *relative_base)?
+            .checked_add(lexer::get_parameter_value(&self.arg2, program, *relative_base)?)
+            .ok_or(IntcodeError::Overflow { ip: 0, word: 0 })?;
+        program.write(lexer::get_parameter_address(&self.output, *relative_base)?, result)?;
+        Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
     }
 
     /// Gets the instruction pointer offset for an add
@@ -49,6 +56,11 @@ impl OpCode for Add {
     fn get_op_code(&self) -> i64 {
         OP_CODE_ID
     }
+
+    /// Gets the parameter that the result is written to
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        Some(&self.output)
+    }
 }
 
 
@@ -64,13 +76,17 @@ fn test_parse_add_from_slice() {
 
 #[test]
 fn test_apply_add() {
-    let mut program: Vec<i64> = vec![10001, 1, 2, 0];
-    let instruction = Add::parse_from_slice(&program).unwrap();
-    instruction.apply(&mut program, 0, || 0, |_x| () );
-    assert_eq!(3, program[0]);
+    let mut program = Memory::new(vec![1, 1, 2, 0]);
+    let instruction = Add::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut QueueIo::default()), Ok(4));
+    assert_eq!(3, program.read(0).unwrap());
+
+    let mut program = Memory::new(vec![1101, 5, 10, 0]);
+    let instruction = Add::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    instruction.apply(&mut program, 0, &mut 0, &mut QueueIo::default()).unwrap();
+    assert_eq!(15, program.read(0).unwrap());
 
-    let mut program: Vec<i64> = vec![11101, 5, 10, 0];
-    let instruction = Add::parse_from_slice(&program).unwrap();
-    instruction.apply(&mut program, 0, || 0, |_x| () );
-    assert_eq!(15, program[0]);
+    let mut program = Memory::new(vec![10001, 1, 2, 0]);
+    let instruction = Add::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut QueueIo::default()), Err(IntcodeError::WriteToImmediate { ip: 0, word: 0 }));
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
+use crate::lexer;
+use crate::memory::Memory;
+#[allow(unused_imports)]
+use crate::parameters::ParameterMode;
+#[allow(unused_imports)]
+use crate::io::QueueIo;
+
+const OP_CODE_ID: i64 = 9;
+const INSTRUCTION_POINTER_OFFSET: i64 = 2;
+
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
+pub struct AdjustRelativeBase { 
+    arg: lexer::Parameter
+}
+
+impl OpCode for AdjustRelativeBase {
+    /// Parses an adjust relative base instruction from a slice of a program
+    fn parse_from_slice(program: &[i64]) -> Option<AdjustRelativeBase> {
+        if program.len() < 2 {
+            return None
+        }
+
+        let mut operation = match lexer::parse_instruction_type(program[0]) {
+            Some(lexer::InstructionType{ op_code: OP_CODE_ID, a: _, b: _, c}) => AdjustRelativeBase{ arg: c},
+            Some(_i) => return None,
+            None => return None
+        };
+        operation.arg.value = program[1];
+        Some(operation)
+    }
+
+    /// Applies an adjust relative base operation on a program
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        *relative_base = relative_base.checked_add(lexer::get_parameter_value(&self.arg, program, *relative_base)?)
+            .ok_or(IntcodeError::Overflow { ip: 0, word: 0 })?;
+        Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
+    }
+
+    /// Gets the instruction pointer offset for an adjust relative base
+    fn get_instruction_pointer_offset(&self) -> i64 {
+        INSTRUCTION_POINTER_OFFSET
+    }
+
+    /// Gets the operation code
+    fn get_op_code(&self) -> i64 {
+        OP_CODE_ID
+    }
+}
+
+
+#[test]
+fn test_parse_adjust_relative_base_from_slice() {    
+    let i1 = AdjustRelativeBase::parse_from_slice(&[109, 19]).unwrap();
+    assert_eq!(i1, AdjustRelativeBase{ arg: lexer::Parameter{ mode: ParameterMode::Immediate, value: 19}});
+
+    let i2 = AdjustRelativeBase::parse_from_slice(&[209, -3]).unwrap();
+    assert_eq!(i2, AdjustRelativeBase{ arg: lexer::Parameter{ mode: ParameterMode::Relative, value: -3}});
+
+    assert!(AdjustRelativeBase::parse_from_slice(&[109]).is_none());
+}
+
+#[test]
+fn test_apply_adjust_relative_base() {
+    let mut program = Memory::new(vec![109, 19]);
+    let mut relative_base = 2000;
+    let i1 = AdjustRelativeBase::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(i1.apply(&mut program, 0, &mut relative_base, &mut QueueIo::default()), Ok(2));
+    assert_eq!(relative_base, 2019);
+
+    let mut program = Memory::new(vec![9, 2, -7]);
+    let i1 = AdjustRelativeBase::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    i1.apply(&mut program, 0, &mut relative_base, &mut QueueIo::default()).unwrap();
+    assert_eq!(relative_base, 2012);
+}
@@ -1,16 +1,19 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 
 const OP_CODE_ID: i64 = 99;
 const INSTRUCTION_POINTER_OFFSET: i64 = 1;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct Complete { }
 
 impl OpCode for Complete {
     /// Parses a complete instruction from a slice of a program
     fn parse_from_slice(program: &[i64]) -> Option<Complete> {        
-        match lexer::parse_instruction_type(program[0]) {
+        match lexer::parse_instruction_type(*program.first()?) {
             Some(lexer::InstructionType{ op_code: OP_CODE_ID, a: _, b: _, c: _}) => Some(Complete{}),
             Some(_i) => None,
             None => None
@@ -18,8 +21,13 @@ impl OpCode for Complete {
     }
 
     /// Applies a complete operation on a program
-    fn apply(&self, _program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        instruction_pointer + INSTRUCTION_POINTER_OFFSET
+    ///
+    /// ## Note
+    /// Leaves the instruction pointer on the halt, so a program that has completed stays completed
+    /// however many times it is stepped. The machine moves into `RunState::Halted` instead of
+    /// applying it
+    fn apply(&self, _program: &mut Memory, instruction_pointer: i64, _relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        Ok(instruction_pointer)
     }
 
     /// Gets the instruction pointer offset for a complete
@@ -40,3 +48,10 @@ fn test_parse_complete_from_slice() {
     Complete::parse_from_slice(&vec![10199, 1, 2, 3]).unwrap();
     Complete::parse_from_slice(&vec![99, 1, 2, 3]).unwrap();
 }
+
+#[test]
+fn test_apply_complete() {
+    let mut program = Memory::new(vec![99, 1, 2, 3]);
+    let instruction = Complete::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut crate::io::QueueIo::default()), Ok(0));
+}
@@ -1,10 +1,13 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 
 const OP_CODE_ID: i64 = 8;
 const INSTRUCTION_POINTER_OFFSET: i64 = 4;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct Equals { 
     arg1: lexer::Parameter,
     arg2: lexer::Parameter,
@@ -14,6 +17,10 @@ pub struct Equals {
 impl OpCode for Equals {
     /// Parses an output instruction from a slice of a program
     fn parse_from_slice(program: &[i64]) -> Option<Equals> {        
+        if program.len() < 4 {
+            return None
+        }
+
         let mut operation = match lexer::parse_instruction_type(program[0]) {
             Some(lexer::InstructionType{ op_code: OP_CODE_ID, a, b, c}) => Equals{ arg1: c, arg2: b, output: a},
             Some(_i) => return None,
@@ -26,15 +33,15 @@ impl OpCode for Equals {
     }
 
     /// Applies a less than compare store operation on a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        let value_1 = lexer::get_parameter_value(&self.arg1, &program);
-        let value_2 = lexer::get_parameter_value(&self.arg2, &program);        
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let value_1 = lexer::get_parameter_value(&self.arg1, program, *relative_base)?;
+        let value_2 = lexer::get_parameter_value(&self.arg2, program, *relative_base)?;        
         if value_1 == value_2 {
-            program[self.output.value as usize] = 1;
+            program.write(lexer::get_parameter_address(&self.output, *relative_base)?, 1)?;
         } else {
-            program[self.output.value as usize] = 0;
+            program.write(lexer::get_parameter_address(&self.output, *relative_base)?, 0)?;
         }
-        instruction_pointer + INSTRUCTION_POINTER_OFFSET
+        Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
     }
 
     /// Gets the instruction pointer offset for a complete
@@ -46,4 +53,9 @@ impl OpCode for Equals {
     fn get_op_code(&self) -> i64 {
         OP_CODE_ID
     }
+
+    /// Gets the parameter that the result is written to
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        Some(&self.output)
+    }
 }
\ No newline at end of file
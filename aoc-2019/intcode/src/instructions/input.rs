@@ -1,12 +1,17 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 #[allow(unused_imports)]
 use crate::parameters::ParameterMode;
+#[allow(unused_imports)]
+use crate::io::QueueIo;
 
 const OP_CODE_ID: i64 = 3;
 const INSTRUCTION_POINTER_OFFSET: i64 = 2;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct Input { 
     arg: lexer::Parameter
 }
@@ -14,6 +19,10 @@ pub struct Input {
 impl OpCode for Input {
     /// Parses an input instruction from a slice of a program
     fn parse_from_slice(program: &[i64]) -> Option<Input> {        
+        if program.len() < 2 {
+            return None
+        }
+
         let mut operation = match lexer::parse_instruction_type(program[0]) {
             Some(lexer::InstructionType{ op_code: OP_CODE_ID, a: _, b: _, c}) => Input{ arg: c},
             Some(_i) => return None,
@@ -24,10 +33,15 @@ impl OpCode for Input {
     }
 
     /// Applies an input operation on a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        lexer::get_parameter_value(&self.arg, &program);        
-        program[self.arg.value as usize] = input();
-        instruction_pointer + INSTRUCTION_POINTER_OFFSET
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let address = lexer::get_parameter_address(&self.arg, *relative_base)?;
+        match io.read() {
+            Some(value) => {
+                program.write(address, value)?;
+                Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
+            },
+            None => Err(IntcodeError::InputExhausted { ip: 0, word: 0 })
+        }
     }
 
     /// Gets the instruction pointer offset for a complete
@@ -39,6 +53,11 @@ impl OpCode for Input {
     fn get_op_code(&self) -> i64 {
         OP_CODE_ID
     }
+
+    /// Gets the parameter that the result is written to
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        Some(&self.arg)
+    }
 }
 
 
@@ -53,13 +72,21 @@ fn test_parse_input_from_slice() {
 
 #[test]
 fn test_apply_input() {
-    let mut program: Vec<i64> = vec![3, 3, 2, 0];
-    let i1 = Input::parse_from_slice(&program).unwrap();
-    i1.apply(&mut program, 0, || 69, |_x| ());
-    assert_eq!(program[3], 69);
+    let mut program = Memory::new(vec![3, 3, 2, 0]);
+    let i1 = Input::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(i1.apply(&mut program, 0, &mut 0, &mut QueueIo::new(vec![69])), Ok(2));
+    assert_eq!(program.read(3), Ok(69));
 
-    let mut program: Vec<i64> = vec![3, 1, 2, 0];
-    let i1 = Input::parse_from_slice(&program).unwrap();
-    i1.apply(&mut program, 0, || 69, |_x| ());
-    assert_eq!(program[1], 69);
+    let mut program = Memory::new(vec![3, 1, 2, 0]);
+    let i1 = Input::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    i1.apply(&mut program, 0, &mut 0, &mut QueueIo::new(vec![69])).unwrap();
+    assert_eq!(program.read(1), Ok(69));
+}
+
+#[test]
+fn test_apply_input_without_available_input() {
+    let mut program = Memory::new(vec![3, 3, 2, 0]);
+    let i1 = Input::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(i1.apply(&mut program, 0, &mut 0, &mut QueueIo::default()), Err(IntcodeError::InputExhausted { ip: 0, word: 0 }));
+    assert_eq!(program.to_vec(), Ok(vec![3, 3, 2, 0]));
 }
\ No newline at end of file
@@ -1,10 +1,13 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 
 const OP_CODE_ID: i64 = 6;
 const INSTRUCTION_POINTER_OFFSET: i64 = 3;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct JumpIfFalse { 
     arg1: lexer::Parameter,
     arg2: lexer::Parameter
@@ -13,6 +16,10 @@ pub struct JumpIfFalse {
 impl OpCode for JumpIfFalse {
     /// Parses an output instruction from a slice of a program
     fn parse_from_slice(program: &[i64]) -> Option<JumpIfFalse> {        
+        if program.len() < 3 {
+            return None
+        }
+
         let mut operation = match lexer::parse_instruction_type(program[0]) {
             Some(lexer::InstructionType{ op_code: OP_CODE_ID, a: _, b, c}) => JumpIfFalse{ arg1: c, arg2: b},
             Some(_i) => return None,
@@ -24,13 +31,13 @@ impl OpCode for JumpIfFalse {
     }
 
     /// Applies a jump if false compare operation on a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        let cmp = lexer::get_parameter_value(&self.arg1, &program);
-        let jump_to = lexer::get_parameter_value(&self.arg2, &program);        
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let cmp = lexer::get_parameter_value(&self.arg1, program, *relative_base)?;
+        let jump_to = lexer::get_parameter_value(&self.arg2, program, *relative_base)?;        
         if cmp == 0 {
-            jump_to
+            Ok(jump_to)
         } else {
-            instruction_pointer + INSTRUCTION_POINTER_OFFSET
+            Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
         }
     }
 
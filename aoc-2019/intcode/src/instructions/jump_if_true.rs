@@ -1,10 +1,13 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 
 const OP_CODE_ID: i64 = 5;
 const INSTRUCTION_POINTER_OFFSET: i64 = 3;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct JumpIfTrue { 
     arg1: lexer::Parameter,
     arg2: lexer::Parameter
@@ -13,6 +16,10 @@ pub struct JumpIfTrue {
 impl OpCode for JumpIfTrue {
     /// Parses an output instruction from a slice of a program
     fn parse_from_slice(program: &[i64]) -> Option<JumpIfTrue> {        
+        if program.len() < 3 {
+            return None
+        }
+
         let mut operation = match lexer::parse_instruction_type(program[0]) {
             Some(lexer::InstructionType{ op_code: OP_CODE_ID, a: _, b, c}) => JumpIfTrue{ arg1: c, arg2: b},
             Some(_i) => return None,
@@ -24,13 +31,13 @@ impl OpCode for JumpIfTrue {
     }
 
     /// Applies a jump if true compare operation on a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        let cmp = lexer::get_parameter_value(&self.arg1, &program);
-        let jump_to = lexer::get_parameter_value(&self.arg2, &program);
-        if cmp > 0 {
-            jump_to
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let cmp = lexer::get_parameter_value(&self.arg1, program, *relative_base)?;
+        let jump_to = lexer::get_parameter_value(&self.arg2, program, *relative_base)?;
+        if cmp != 0 {
+            Ok(jump_to)
         } else {
-            instruction_pointer + INSTRUCTION_POINTER_OFFSET
+            Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
         }
     }
 
@@ -43,4 +50,16 @@ impl OpCode for JumpIfTrue {
     fn get_op_code(&self) -> i64 {
         OP_CODE_ID
     }
-}
\ No newline at end of file
+}
+
+
+#[test]
+fn test_apply_jump_if_true() {
+    let mut program = Memory::new(vec![1105, -1, 9]);
+    let instruction = JumpIfTrue::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut crate::io::QueueIo::default()), Ok(9));
+
+    let mut program = Memory::new(vec![1105, 0, 9]);
+    let instruction = JumpIfTrue::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut crate::io::QueueIo::default()), Ok(3));
+}
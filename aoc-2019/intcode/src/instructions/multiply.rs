@@ -1,12 +1,17 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 #[allow(unused_imports)]
 use crate::parameters::ParameterMode;
+#[allow(unused_imports)]
+use crate::io::QueueIo;
 
 const OP_CODE_ID: i64 = 2;
 const INSTRUCTION_POINTER_OFFSET: i64 = 4;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct Multiply {
     arg1: lexer::Parameter,
     arg2: lexer::Parameter,
@@ -33,10 +38,12 @@ impl OpCode for Multiply {
     }
 
     /// Applies a multiply operation on a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, _output: fn(i64) -> ()) -> i64 {
-        let result = lexer::get_parameter_value(&self.arg1, &program) * lexer::get_parameter_value(&self.arg2, &program);     
-        program[self.output.value as usize] = result;
-        instruction_pointer + INSTRUCTION_POINTER_OFFSET
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let result = lexer::get_parameter_value(&self.arg1, program, *relative_base)?
+            .checked_mul(lexer::get_parameter_value(&self.arg2, program, *relative_base)?)
+            .ok_or(IntcodeError::Overflow { ip: 0, word: 0 })?;
+        program.write(lexer::get_parameter_address(&self.output, *relative_base)?, result)?;
+        Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
     }
 
     /// Gets the instruction pointer offset for a multiply
@@ -48,6 +55,11 @@ impl OpCode for Multiply {
     fn get_op_code(&self) -> i64 {
         OP_CODE_ID
     }
+
+    /// Gets the parameter that the result is written to
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        Some(&self.output)
+    }
 }
 
 
@@ -69,13 +81,17 @@ fn test_parse_multiply_from_slice() {
 
 #[test]
 fn test_apply_multiply() {
-    let mut program: Vec<i64> = vec![10002, 1, 2, 0];
-    let instruction = Multiply::parse_from_slice(&program).unwrap();
-    instruction.apply(&mut program, 0, || 0, |_x| () );
-    assert_eq!(2, program[0]);
-
-    let mut program: Vec<i64> = vec![11102, 5, 10, 0];
-    let instruction = Multiply::parse_from_slice(&program).unwrap();
-    instruction.apply(&mut program, 0, || 0, |_x| () );
-    assert_eq!(50, program[0]);
+    let mut program = Memory::new(vec![2, 1, 2, 0]);
+    let instruction = Multiply::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut QueueIo::default()), Ok(4));
+    assert_eq!(2, program.read(0).unwrap());
+
+    let mut program = Memory::new(vec![1102, 5, 10, 0]);
+    let instruction = Multiply::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    instruction.apply(&mut program, 0, &mut 0, &mut QueueIo::default()).unwrap();
+    assert_eq!(50, program.read(0).unwrap());
+
+    let mut program = Memory::new(vec![11102, 5, 10, 0]);
+    let instruction = Multiply::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    assert_eq!(instruction.apply(&mut program, 0, &mut 0, &mut QueueIo::default()), Err(IntcodeError::WriteToImmediate { ip: 0, word: 0 }));
 }
\ No newline at end of file
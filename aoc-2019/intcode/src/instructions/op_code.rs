@@ -1,3 +1,7 @@
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
+use crate::lexer;
+use crate::memory::Memory;
 use num_derive::FromPrimitive;
 
 /// Common trait methods that an operation must satisfy
@@ -9,14 +13,19 @@ pub trait OpCode {
     fn get_instruction_pointer_offset(&self) -> i64;
 
     /// Applies an instruction to a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, input: fn() -> i64, output: fn(i64) -> ()) -> i64;
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError>;
 
     /// Gets the op code from an operation
     fn get_op_code(&self) -> i64;
+
+    /// Gets the parameter that the operation writes its result to, if it has one
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        None
+    }
 }
 
 /// Enumeration of op code IDs
-#[derive(FromPrimitive)]
+#[derive(FromPrimitive, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum OpCodeId {
     Add = 1,
     Multiply = 2,
@@ -26,5 +35,6 @@ pub enum OpCodeId {
     JumpIfFalse = 6,
     LessThan = 7,
     Equals = 8,
+    AdjustRelativeBase = 9,
     Complete = 99
 }
@@ -1,12 +1,17 @@
 use crate::instructions::op_code::OpCode;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
 use crate::lexer;
+use crate::memory::Memory;
 #[allow(unused_imports)]
 use crate::parameters::ParameterMode;
+#[allow(unused_imports)]
+use crate::io::QueueIo;
 
 const OP_CODE_ID: i64 = 4;
 const INSTRUCTION_POINTER_OFFSET: i64 = 2;
 
-#[derive(PartialEq, Debug, Default)]
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
 pub struct Output { 
     arg: lexer::Parameter
 }
@@ -14,6 +19,10 @@ pub struct Output {
 impl OpCode for Output {
     /// Parses an output instruction from a slice of a program
     fn parse_from_slice(program: &[i64]) -> Option<Output> {        
+        if program.len() < 2 {
+            return None
+        }
+
         let mut operation = match lexer::parse_instruction_type(program[0]) {
             Some(lexer::InstructionType{ op_code: OP_CODE_ID, a: _, b: _, c}) => Output{ arg: c},
             Some(_i) => return None,
@@ -24,10 +33,10 @@ impl OpCode for Output {
     }
 
     /// Applies an output operation on a program
-    fn apply(&self, program: &mut [i64], instruction_pointer: i64, _input: fn() -> i64, output: fn(i64) -> ()) -> i64 {
-        let value = lexer::get_parameter_value(&self.arg, &program);
-        output(value);
-        instruction_pointer + INSTRUCTION_POINTER_OFFSET
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let value = lexer::get_parameter_value(&self.arg, program, *relative_base)?;
+        io.write(value);
+        Ok(instruction_pointer + INSTRUCTION_POINTER_OFFSET)
     }
 
     /// Gets the instruction pointer offset for a complete
@@ -53,11 +62,14 @@ fn test_parse_output_from_slice() {
 
 #[test]
 fn test_apply_output() {
-    let mut program: Vec<i64> = vec![4, 3, 2, 0];
-    let i1 = Output::parse_from_slice(&program).unwrap();    
-    i1.apply(&mut program, 0, || 69, |x| assert_eq!(x, 0));    
+    let mut program = Memory::new(vec![4, 3, 2, 0]);
+    let i1 = Output::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    let mut io = QueueIo::default();
+    i1.apply(&mut program, 0, &mut 0, &mut io).unwrap();
+    assert_eq!(io.pop_output(), Some(0));
 
-    let mut program: Vec<i64> = vec![104, 1, 2, 0];
-    let i1 = Output::parse_from_slice(&program).unwrap();
-    i1.apply(&mut program, 0, || 69, |x| assert_eq!(x, 1));    
+    let mut program = Memory::new(vec![104, 1, 2, 0]);
+    let i1 = Output::parse_from_slice(&program.to_vec().unwrap()).unwrap();
+    i1.apply(&mut program, 0, &mut 0, &mut io).unwrap();
+    assert_eq!(io.pop_output(), Some(1));
 }
\ No newline at end of file
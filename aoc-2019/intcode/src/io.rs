@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Source of input values and sink for output values used by the input/output instructions
+pub trait IntcodeIo {
+    /// Reads the next input value
+    ///
+    /// Returns `None` when no input is available, in which case the input instruction
+    /// does not advance the instruction pointer
+    fn read(&mut self) -> Option<i64>;
+
+    /// Writes an output value
+    fn write(&mut self, value: i64);
+}
+
+/// Queue backed I/O that reads from a list of inputs and collects every output
+///
+/// # Examples
+/// ```
+/// use intcode::io::{IntcodeIo, QueueIo};
+/// let mut io = QueueIo::new(vec![1, 2]);
+/// assert_eq!(io.read(), Some(1));
+/// io.write(5);
+/// assert_eq!(io.pop_output(), Some(5));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueIo {
+    inputs: VecDeque<i64>,
+    outputs: VecDeque<i64>,
+}
+
+impl QueueIo {
+    /// Creates a new queue from an initial sequence of inputs
+    pub fn new(inputs: impl IntoIterator<Item = i64>) -> QueueIo {
+        QueueIo { inputs: inputs.into_iter().collect(), outputs: VecDeque::new() }
+    }
+
+    /// Appends a value to the end of the input queue
+    pub fn push_input(&mut self, value: i64) {
+        self.inputs.push_back(value);
+    }
+
+    /// Gets the number of inputs that have not been read yet
+    pub fn pending_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Removes and returns the oldest output that has not been read yet
+    pub fn pop_output(&mut self) -> Option<i64> {
+        self.outputs.pop_front()
+    }
+
+    /// Removes and returns every output that has not been read yet
+    pub fn drain_outputs(&mut self) -> Vec<i64> {
+        self.outputs.drain(..).collect()
+    }
+
+    /// Checks if there are any outputs that have not been read yet
+    pub fn has_output(&self) -> bool {
+        !self.outputs.is_empty()
+    }
+}
+
+impl IntcodeIo for QueueIo {
+    fn read(&mut self) -> Option<i64> {
+        self.inputs.pop_front()
+    }
+
+    fn write(&mut self, value: i64) {
+        self.outputs.push_back(value);
+    }
+}
+
+/// I/O that pulls inputs from an iterator and collects every output
+#[derive(Debug)]
+pub struct IterIo<I: Iterator<Item = i64>> {
+    inputs: I,
+    outputs: Vec<i64>,
+}
+
+impl<I: Iterator<Item = i64>> IterIo<I> {
+    /// Creates a new I/O from anything that can be iterated into inputs
+    pub fn new(inputs: impl IntoIterator<IntoIter = I>) -> IterIo<I> {
+        IterIo { inputs: inputs.into_iter(), outputs: Vec::new() }
+    }
+
+    /// Gets every output collected so far
+    pub fn outputs(&self) -> &[i64] {
+        &self.outputs
+    }
+
+    /// Consumes the I/O and returns the collected outputs
+    pub fn into_outputs(self) -> Vec<i64> {
+        self.outputs
+    }
+}
+
+impl<I: Iterator<Item = i64>> IntcodeIo for IterIo<I> {
+    fn read(&mut self) -> Option<i64> {
+        self.inputs.next()
+    }
+
+    fn write(&mut self, value: i64) {
+        self.outputs.push(value);
+    }
+}
+
+/// I/O built from a pair of closures
+///
+/// # Examples
+/// ```
+/// use intcode::io::FnIo;
+/// let mut program = vec![3, 0, 4, 0, 99];
+/// intcode::run_interpreter(&mut program, &mut FnIo::new(|| 1, |x| println!("Output: {}", x))).unwrap();
+/// ```
+pub struct FnIo<I: FnMut() -> i64, O: FnMut(i64)> {
+    input: I,
+    output: O,
+}
+
+impl<I: FnMut() -> i64, O: FnMut(i64)> FnIo<I, O> {
+    /// Creates a new I/O from an input and an output closure
+    pub fn new(input: I, output: O) -> FnIo<I, O> {
+        FnIo { input, output }
+    }
+}
+
+impl<I: FnMut() -> i64, O: FnMut(i64)> IntcodeIo for FnIo<I, O> {
+    fn read(&mut self) -> Option<i64> {
+        Some((self.input)())
+    }
+
+    fn write(&mut self, value: i64) {
+        (self.output)(value)
+    }
+}
+
+/// I/O over a pair of channels so that machines can run on separate threads
+///
+/// Reads block until a value arrives and return `None` once the sending side has hung up.
+/// Writes to a receiver that has hung up are dropped.
+#[derive(Debug)]
+pub struct ChannelIo {
+    receiver: Receiver<i64>,
+    sender: Sender<i64>,
+}
+
+impl ChannelIo {
+    /// Creates a new I/O that reads from `receiver` and writes to `sender`
+    pub fn new(receiver: Receiver<i64>, sender: Sender<i64>) -> ChannelIo {
+        ChannelIo { receiver, sender }
+    }
+}
+
+impl IntcodeIo for ChannelIo {
+    fn read(&mut self) -> Option<i64> {
+        self.receiver.recv().ok()
+    }
+
+    fn write(&mut self, value: i64) {
+        let _ = self.sender.send(value);
+    }
+}
+
+/// I/O that reads one integer per line from stdin and prints outputs to stdout
+#[derive(Debug, Default)]
+pub struct StdIo { }
+
+impl IntcodeIo for StdIo {
+    fn read(&mut self) -> Option<i64> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => ()
+            }
+            match line.trim().parse::<i64>() {
+                Ok(value) => return Some(value),
+                Err(_) => eprintln!("Expected an integer, got {:?}", line.trim())
+            }
+        }
+    }
+
+    fn write(&mut self, value: i64) {
+        println!("{}", value);
+    }
+}
+
+
+#[test]
+fn test_queue_io() {
+    let mut io = QueueIo::new(vec![1, 2]);
+    assert_eq!(io.read(), Some(1));
+    io.push_input(3);
+    assert_eq!(io.pending_inputs(), 2);
+    assert_eq!(io.read(), Some(2));
+    assert_eq!(io.read(), Some(3));
+    assert_eq!(io.read(), None);
+
+    io.write(4);
+    io.write(5);
+    assert_eq!(io.pop_output(), Some(4));
+    assert_eq!(io.drain_outputs(), vec![5]);
+    assert!(!io.has_output());
+}
+
+#[test]
+fn test_iter_io() {
+    let mut io = IterIo::new(1..3);
+    assert_eq!(io.read(), Some(1));
+    assert_eq!(io.read(), Some(2));
+    assert_eq!(io.read(), None);
+    io.write(9);
+    assert_eq!(io.into_outputs(), vec![9]);
+}
+
+#[test]
+fn test_channel_io() {
+    let (input_tx, input_rx) = std::sync::mpsc::channel();
+    let (output_tx, output_rx) = std::sync::mpsc::channel();
+    let mut io = ChannelIo::new(input_rx, output_tx);
+    input_tx.send(10).unwrap();
+    assert_eq!(io.read(), Some(10));
+    io.write(11);
+    assert_eq!(output_rx.recv(), Ok(11));
+
+    drop(input_tx);
+    assert_eq!(io.read(), None);
+}
@@ -1,14 +1,17 @@
+use crate::error::IntcodeError;
+use crate::memory::Memory;
 use crate::parameters::ParameterMode;
+use num_traits::FromPrimitive;
 
 /// Encodes a parameter as well as it's calling type
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
 pub struct Parameter {
     pub mode: ParameterMode,
     pub value: i64
 }
 
 /// Encodes an intcode instruction and it's parameter passing modes
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct InstructionType {
     pub op_code: i64,
     pub a: Parameter,
@@ -20,7 +23,8 @@ pub struct InstructionType {
 /// 
 /// ## Note
 /// Always parses three arguments regardless of the operation, each operation
-/// must specifically reduce this to what it requires as necessary
+/// must specifically reduce this to what it requires as necessary. Negative words, words
+/// with more than five digits and unknown mode digits return None
 /// 
 /// # Examples
 /// ```ignore
@@ -28,44 +32,57 @@ pub struct InstructionType {
 /// // instruction = Some(2, Position, Immediate, Position)
 /// ```
 pub fn parse_instruction_type(instruction: i64) -> Option<InstructionType> {
-    let mut chars: Vec<char> = instruction.to_string().chars().collect();
-    while chars.len() < 5 {
-        chars.insert(0, '0');
+    if !(0..100_000).contains(&instruction) {
+        return None;
     }
-    let a = parse_parameter_mode(chars[0])?;
-    let b = parse_parameter_mode(chars[1])?;
-    let c = parse_parameter_mode(chars[2])?;
-    let op_code = chars.iter().skip(3).collect::<String>().parse::<i64>().unwrap();
-    Some(InstructionType{ op_code, 
+    let a = parse_parameter_mode(instruction / 10_000 % 10)?;
+    let b = parse_parameter_mode(instruction / 1_000 % 10)?;
+    let c = parse_parameter_mode(instruction / 100 % 10)?;
+    Some(InstructionType{ op_code: instruction % 100,
                           a: Parameter{mode: a, value: 0},
                           b: Parameter{mode: b, value: 0},
-                          c: Parameter{mode: c, value: 0}})    
+                          c: Parameter{mode: c, value: 0}})
 }
 
 /// Gets the actual value of a parameter depending on the parameter mode
 /// 
 /// # Examples
 /// ```ignore
-/// assert_eq!(get_parameter_value( Parameter{ mode: ParameterMode::Position, value: 3}, &vec![10, 20, 30, 40]), 40);
-/// assert_eq!(get_parameter_value( Parameter{ mode: ParameterMode::Immediate, value: 3}, &vec![10, 20, 30, 40]), 3);
+/// assert_eq!(get_parameter_value( Parameter{ mode: ParameterMode::Position, value: 3}, &Memory::new(vec![10, 20, 30, 40]), 0), Ok(40));
+/// assert_eq!(get_parameter_value( Parameter{ mode: ParameterMode::Immediate, value: 3}, &Memory::new(vec![10, 20, 30, 40]), 0), Ok(3));
+/// assert_eq!(get_parameter_value( Parameter{ mode: ParameterMode::Relative, value: -1}, &Memory::new(vec![10, 20, 30, 40]), 2), Ok(20));
 /// ```
-pub fn get_parameter_value(param: &Parameter, program: &[i64]) -> i64 {
+pub fn get_parameter_value(param: &Parameter, program: &Memory, relative_base: i64) -> Result<i64, IntcodeError> {
     match param.mode {
-        ParameterMode::Position => program[param.value as usize],
-        ParameterMode::Immediate => param.value
+        ParameterMode::Position | ParameterMode::Relative => program.read(get_parameter_address(param, relative_base)?),
+        ParameterMode::Immediate => Ok(param.value)
     }
 }
 
-
-/// Helper function to match a char and convert it to a ParameterMode
-fn parse_parameter_mode(c: char) -> Option<ParameterMode> {
-    match c {
-        '0' => Some(ParameterMode::Position),
-        '1' => Some(ParameterMode::Immediate),
-        _   => None
+/// Gets the memory address that a parameter refers to depending on the parameter mode
+/// 
+/// ## Note
+/// Used for the parameters that instructions write to, which can not be in immediate mode
+/// 
+/// # Examples
+/// ```ignore
+/// assert_eq!(get_parameter_address( Parameter{ mode: ParameterMode::Position, value: 3}, 10), Ok(3));
+/// assert_eq!(get_parameter_address( Parameter{ mode: ParameterMode::Relative, value: 3}, 10), Ok(13));
+/// ```
+pub fn get_parameter_address(param: &Parameter, relative_base: i64) -> Result<i64, IntcodeError> {
+    match param.mode {
+        ParameterMode::Position => Ok(param.value),
+        ParameterMode::Relative => relative_base.checked_add(param.value).ok_or(IntcodeError::Overflow { ip: 0, word: 0 }),
+        ParameterMode::Immediate => Err(IntcodeError::WriteToImmediate { ip: 0, word: 0 })
     }
 }
 
+
+/// Helper function to convert a single decimal digit to a ParameterMode
+fn parse_parameter_mode(digit: i64) -> Option<ParameterMode> {
+    FromPrimitive::from_i64(digit)
+}
+
 #[test]
 fn test_parse_instruction_type() {
     assert_eq!(parse_instruction_type(1002), Some(InstructionType{ op_code: 2, 
@@ -77,17 +94,38 @@ fn test_parse_instruction_type() {
             a: Parameter{ mode: ParameterMode::Position, value: 0}, 
             b: Parameter{ mode: ParameterMode::Immediate, value: 0},
             c: Parameter{ mode: ParameterMode::Position, value: 0}}));
+
+    assert_eq!(parse_instruction_type(21201), Some(InstructionType{ op_code: 1, 
+            a: Parameter{ mode: ParameterMode::Relative, value: 0}, 
+            b: Parameter{ mode: ParameterMode::Immediate, value: 0},
+            c: Parameter{ mode: ParameterMode::Relative, value: 0}}));
+
+    assert_eq!(parse_instruction_type(99).map(|i| i.op_code), Some(99));
+    assert_eq!(parse_instruction_type(30001), None);
+    assert_eq!(parse_instruction_type(100001), None);
+    assert_eq!(parse_instruction_type(-1), None);
 }
 
 #[test]
 fn test_parse_parameter_mode() {
-    assert_eq!(parse_parameter_mode('0'), Some(ParameterMode::Position));
-    assert_eq!(parse_parameter_mode('1'), Some(ParameterMode::Immediate));
-    assert_eq!(parse_parameter_mode('2'), None);
+    assert_eq!(parse_parameter_mode(0), Some(ParameterMode::Position));
+    assert_eq!(parse_parameter_mode(1), Some(ParameterMode::Immediate));
+    assert_eq!(parse_parameter_mode(2), Some(ParameterMode::Relative));
+    assert_eq!(parse_parameter_mode(3), None);
 }
 
 #[test]
 fn test_get_parameter_value() {
-    assert_eq!(get_parameter_value( &Parameter{ mode: ParameterMode::Position, value: 3}, &vec![10, 20, 30, 40]), 40);
-    assert_eq!(get_parameter_value( &Parameter{ mode: ParameterMode::Immediate, value: 3}, &vec![10, 20, 30, 40]), 3);
+    assert_eq!(get_parameter_value( &Parameter{ mode: ParameterMode::Position, value: 3}, &Memory::new(vec![10, 20, 30, 40]), 0), Ok(40));
+    assert_eq!(get_parameter_value( &Parameter{ mode: ParameterMode::Immediate, value: 3}, &Memory::new(vec![10, 20, 30, 40]), 0), Ok(3));
+    assert_eq!(get_parameter_value( &Parameter{ mode: ParameterMode::Relative, value: -1}, &Memory::new(vec![10, 20, 30, 40]), 2), Ok(20));
+    assert_eq!(get_parameter_value( &Parameter{ mode: ParameterMode::Relative, value: -3}, &Memory::new(vec![10, 20, 30, 40]), 2),
+               Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address: -1 }));
+}
+
+#[test]
+fn test_get_parameter_address() {
+    assert_eq!(get_parameter_address( &Parameter{ mode: ParameterMode::Position, value: 3}, 10), Ok(3));
+    assert_eq!(get_parameter_address( &Parameter{ mode: ParameterMode::Immediate, value: 3}, 10), Err(IntcodeError::WriteToImmediate { ip: 0, word: 0 }));
+    assert_eq!(get_parameter_address( &Parameter{ mode: ParameterMode::Relative, value: 3}, 10), Ok(13));
 }
\ No newline at end of file
@@ -1,76 +1,99 @@
+#![cfg_attr(test, allow(clippy::useless_vec, clippy::bool_assert_comparison))]
+
+pub mod amplifiers;
+pub mod analysis;
+pub mod ascii;
+pub mod assembler;
+pub mod cache;
+pub mod debugger;
+pub mod disassembler;
+pub mod error;
+pub mod grid;
 pub mod instructions;
+pub mod io;
 pub mod lexer;
+pub mod loader;
+pub mod machine;
+pub mod memory;
+#[cfg(feature = "async")]
+pub mod network;
 pub mod parameters;
+pub mod profiler;
+pub mod registry;
+pub mod search;
+pub mod snapshot;
+pub mod threaded;
+pub mod trace;
 
-use crate::instructions::parse_from_slice;
+use crate::error::IntcodeError;
+use crate::io::IntcodeIo;
+use crate::machine::{ExitReason, Intcode, RunOutcome};
 
-use std::{
-    fs::File,
-    io::{self, BufRead, BufReader},
-    path::Path,
-};
+use std::{fs::File, path::Path};
 
 /// Loads an intcode program from a file path
+///
+/// The file may hold the program as text or in the binary format, see the `loader` module
 /// 
 /// # Examples
-/// ```ignoreuse crate::parameters::ParameterMode;
+/// ```ignore
 /// let program = intcode::load_program_from_file("file.txt").expect("Could not load program");
 /// ```
-pub fn load_program_from_file(filename: impl AsRef<Path>) -> Result<Vec<i64>, String> {
-    let lines = match load_lines_from_file(filename) {
-        Ok(l) => l,
-        Err(e) => return Err(e.to_string())
-    };
-
-    let instructions = match lines.first() {
-        Some(i) => i,
-        None => return Err(String::from("No instructions found"))
-    };
-    Ok(parse_program_from_lines(&instructions))
+pub fn load_program_from_file(filename: impl AsRef<Path>) -> Result<Vec<i64>, loader::LoadError> {
+    loader::read_program(File::open(filename)?)
 }
 
-/// Loads a vector of strings from a file
-/// 
-/// # Arguments
-/// * `filename` - The path filename
-/// 
+/// Loads an intcode program from the text of a program file, such as one read from stdin
+///
 /// # Examples
-/// ```ignore
-/// let lines = load_lines_from_file("data.txt").expect("Could not load lines");
 /// ```
-fn load_lines_from_file(filename: impl AsRef<Path>) -> io::Result<Vec<String>> {
-    BufReader::new(File::open(filename)?)
-        .lines()
-        .collect()
-}
-
-/// Parses an intcode program from a vector of lines that contains an intcode program
-/// 
-/// # Examples
-/// ```ignore
-/// let program = intcode::parse_program_from_lines(vec![1002,4,3,4]);
+/// assert_eq!(intcode::load_program_from_str("1002,4,3,4,33\n"), Ok(vec![1002, 4, 3, 4, 33]));
 /// ```
-fn parse_program_from_lines(program: &String) -> Vec<i64> {    
-    program.split(",")
-           .map(|x| x.parse::<i64>().unwrap())
-           .collect::<Vec<i64>>()   
+pub fn load_program_from_str(text: &str) -> Result<Vec<i64>, IntcodeError> {
+    loader::parse_program(text)
 }
 
 /// Run the intcode interpreter with a given input and output
 /// 
 /// # Arguments
-/// * `v`      - Slice containing the program data
-/// * `input`  - Function pointer for data input 
-/// * `output` - Function pointer for data output
-pub fn run_interpreter(v: &mut[i64], input: fn() -> i64, output: fn(i64) -> ()) -> () {
-    let mut ip = 0;
-    loop {        
-        let instruction = match lexer::parse_instruction_type(v[ip]) {
-            Some(i) => i,
-            None    => return
-        };
-        // TODO: probably remove the unwrap here
-        let op = parse_from_slice(instruction, &v[ip..]).unwrap();
-        ip = op.apply(&mut v[..], ip as i64, input, output) as usize;
-    }
+/// * `v`  - Vector containing the program data, grown if the program writes past its end
+/// * `io` - Source of inputs and sink for outputs
+/// 
+/// Returns how the halted run went, the fault if the program faults or
+/// `IntcodeError::InputExhausted` if it requests input that the I/O can no longer provide.
+/// The memory is written back to `v` either way
+pub fn run_interpreter(v: &mut Vec<i64>, io: &mut dyn IntcodeIo) -> Result<RunOutcome, IntcodeError> {
+    let mut machine = Intcode::new(std::mem::take(v));
+    let outcome = machine.run(io, None);
+    let result = match outcome.reason {
+        ExitReason::Faulted(e) => Err(e),
+        ExitReason::AwaitingInput => {
+            let ip = outcome.instruction_pointer;
+            Err(IntcodeError::InputExhausted{ ip, word: machine.memory().get(ip) })
+        },
+        ExitReason::Halted | ExitReason::StepLimit => Ok(outcome)
+    };
+    *v = machine.into_memory().into_vec()?;
+    result
+}
+
+#[test]
+fn test_load_program_from_str() {
+    assert_eq!(load_program_from_str("1002,4,3,4,33"), Ok(vec![1002, 4, 3, 4, 33]));
+    assert_eq!(load_program_from_str("1,-2,x3"), Err(IntcodeError::ParseError{ line: 1, column: 6, token: String::from("x3") }));
+}
+
+#[test]
+fn test_run_interpreter() {
+    let mut program = vec![3, 0, 4, 0, 99];
+    let outcome = run_interpreter(&mut program, &mut io::QueueIo::new(vec![5])).unwrap();
+    assert_eq!((outcome.reason, outcome.steps, outcome.instruction_pointer, outcome.outputs), (ExitReason::Halted, 3, 4, vec![5]));
+    assert_eq!(program, vec![5, 0, 4, 0, 99]);
+
+    let mut program = vec![3, 0, 4, 0, 99];
+    assert_eq!(run_interpreter(&mut program, &mut io::QueueIo::default()), Err(IntcodeError::InputExhausted{ ip: 0, word: 3 }));
+
+    let mut program = vec![1, 0, 0, 0, 42];
+    assert_eq!(run_interpreter(&mut program, &mut io::QueueIo::default()), Err(IntcodeError::UnknownOpcode{ ip: 4, word: 42 }));
+    assert_eq!(program, vec![2, 0, 0, 0, 42]);
 }
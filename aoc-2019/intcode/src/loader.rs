@@ -0,0 +1,236 @@
+use crate::error::IntcodeError;
+use std::fmt;
+use std::io::{Read, Write};
+
+/// Header that starts every program stored in the binary format
+pub const BINARY_MAGIC: &[u8; 4] = b"ICB\x01";
+
+/// An error raised while reading or writing a program file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// A program in the text format does not parse
+    Parse(IntcodeError),
+    /// A program in the binary format is malformed at a byte offset
+    InvalidBinary { offset: usize, message: String },
+    /// A program file could not be read or written
+    Io(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Parse(e) => write!(f, "{}", e),
+            LoadError::InvalidBinary { offset, message } => write!(f, "invalid binary program at byte {}: {}", offset, message),
+            LoadError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<IntcodeError> for LoadError {
+    fn from(error: IntcodeError) -> Self {
+        LoadError::Parse(error)
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(error: std::io::Error) -> Self {
+        LoadError::Io(error.to_string())
+    }
+}
+
+/// Parses the text of an intcode program
+///
+/// ## Syntax
+/// * Values are separated by commas, whitespace or line breaks, so a program may span lines
+/// * A trailing comma at the end of a line or of the program is ignored
+/// * `#` starts a comment that runs to the end of the line
+///
+/// Returns `IntcodeError::ParseError` with the one based line and column of the first token
+/// that is not an integer, of a comma with no value before it, such as a leading comma or
+/// two commas in a row even across lines, or of the start of an empty program
+///
+/// # Examples
+/// ```
+/// let program = intcode::loader::parse_program("1002, 4, 3,  # multiply\n 4, 33,\n");
+/// assert_eq!(program, Ok(vec![1002, 4, 3, 4, 33]));
+/// ```
+pub fn parse_program(text: &str) -> Result<Vec<i64>, IntcodeError> {
+    let mut program = Vec::new();
+    // The start of the program counts as a comma so that a leading comma is an empty value
+    let mut after_comma = true;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut token: Option<(usize, String)> = None;
+        for (column, c) in line.chars().chain(std::iter::once(' ')).enumerate() {
+            match c {
+                ',' | ' ' | '\t' | '\r' => {
+                    match token.take() {
+                        Some((start, text)) => program.push(parse_token(&text, i + 1, start + 1)?),
+                        None if c == ',' && after_comma => {
+                            return Err(IntcodeError::ParseError { line: i + 1, column: column + 1, token: String::new() });
+                        },
+                        None => ()
+                    }
+                    if c == ',' {
+                        after_comma = true;
+                    }
+                },
+                _ => {
+                    after_comma = false;
+                    token.get_or_insert_with(|| (column, String::new())).1.push(c);
+                }
+            }
+        }
+    }
+    match program.is_empty() {
+        true => Err(IntcodeError::ParseError { line: 1, column: 1, token: String::new() }),
+        false => Ok(program)
+    }
+}
+
+/// Reads a program from any source, in either the text or the binary format
+///
+/// # Arguments
+/// * `reader` - Source of the program, the format is detected from `BINARY_MAGIC`
+pub fn read_program(mut reader: impl Read) -> Result<Vec<i64>, LoadError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.starts_with(BINARY_MAGIC) {
+        return decode_binary(&bytes);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(parse_program(&text)?),
+        Err(e) => Err(LoadError::Io(format!("program is neither text nor binary: {}", e)))
+    }
+}
+
+/// Encodes a program or memory image in the compact binary format
+///
+/// ## Note
+/// The format is `BINARY_MAGIC`, the number of values as a little endian `u64` and then each
+/// value as a zigzag encoded LEB128 varint, so small values of either sign take a single byte
+///
+/// # Examples
+/// ```
+/// use intcode::loader::{decode_binary, encode_binary};
+/// let program = vec![1002, 4, 3, 4, -33];
+/// assert_eq!(decode_binary(&encode_binary(&program)), Ok(program));
+/// ```
+pub fn encode_binary(program: &[i64]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(BINARY_MAGIC.len() + 8 + program.len() * 2);
+    bytes.extend_from_slice(BINARY_MAGIC);
+    bytes.extend_from_slice(&(program.len() as u64).to_le_bytes());
+    for value in program {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+        while zigzag >= 0x80 {
+            bytes.push((zigzag as u8) | 0x80);
+            zigzag >>= 7;
+        }
+        bytes.push(zigzag as u8);
+    }
+    bytes
+}
+
+/// Decodes a program that was encoded with `encode_binary`
+///
+/// Returns `LoadError::InvalidBinary` with the byte offset of the first malformed value
+pub fn decode_binary(bytes: &[u8]) -> Result<Vec<i64>, LoadError> {
+    let error = |offset: usize, message: &str| LoadError::InvalidBinary { offset, message: message.to_string() };
+    if !bytes.starts_with(BINARY_MAGIC) {
+        return Err(error(0, "missing header"));
+    }
+    let mut offset = BINARY_MAGIC.len();
+    let count = match bytes.get(offset..offset + 8) {
+        Some(count) => u64::from_le_bytes(count.try_into().expect("slice has 8 bytes")),
+        None => return Err(error(offset, "missing value count"))
+    };
+    offset += 8;
+
+    // Every value takes at least one byte, which bounds the allocation for corrupt counts
+    let mut program = Vec::with_capacity((count as usize).min(bytes.len() - offset));
+    for _ in 0..count {
+        let start = offset;
+        let mut zigzag: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = match bytes.get(offset) {
+                Some(b) => *b,
+                None => return Err(error(start, "value is truncated"))
+            };
+            if shift > 63 || (shift == 63 && byte > 1) {
+                return Err(error(start, "value does not fit in 64 bits"));
+            }
+            zigzag |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+            offset += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        program.push((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64));
+    }
+    match offset == bytes.len() {
+        true => Ok(program),
+        false => Err(error(offset, "unexpected data after the last value"))
+    }
+}
+
+/// Writes a program or memory image in the compact binary format
+pub fn write_binary(program: &[i64], mut writer: impl Write) -> Result<(), LoadError> {
+    writer.write_all(&encode_binary(program))?;
+    Ok(())
+}
+
+/// Helper function to parse a single value
+fn parse_token(token: &str, line: usize, column: usize) -> Result<i64, IntcodeError> {
+    token.parse::<i64>().map_err(|_| IntcodeError::ParseError { line, column, token: token.to_string() })
+}
+
+
+#[test]
+fn test_parse_program_layouts() {
+    assert_eq!(parse_program("1,2,3\n"), Ok(vec![1, 2, 3]));
+    assert_eq!(parse_program("1,2,\n3,\r\n\n4"), Ok(vec![1, 2, 3, 4]));
+    assert_eq!(parse_program("  1 ,\t-2 , 3 ,"), Ok(vec![1, -2, 3]));
+    assert_eq!(parse_program("# day 2\n1,0,0,0,  # add\n99 # halt"), Ok(vec![1, 0, 0, 0, 99]));
+    assert_eq!(parse_program("1 2\n3"), Ok(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_parse_program_errors() {
+    assert_eq!(parse_program("1,2\n3,x4,5"), Err(IntcodeError::ParseError { line: 2, column: 3, token: String::from("x4") }));
+    assert_eq!(parse_program("1,,2"), Err(IntcodeError::ParseError { line: 1, column: 3, token: String::new() }));
+    assert_eq!(parse_program("1, ,2"), Err(IntcodeError::ParseError { line: 1, column: 4, token: String::new() }));
+    assert_eq!(parse_program(",1,2"), Err(IntcodeError::ParseError { line: 1, column: 1, token: String::new() }));
+    assert_eq!(parse_program("# header
+  ,1"), Err(IntcodeError::ParseError { line: 2, column: 3, token: String::new() }));
+    assert_eq!(parse_program("1,2,
+,3"), Err(IntcodeError::ParseError { line: 2, column: 1, token: String::new() }));
+    assert_eq!(parse_program("1,2,,"), Err(IntcodeError::ParseError { line: 1, column: 5, token: String::new() }));
+    assert_eq!(parse_program("99999999999999999999"), Err(IntcodeError::ParseError { line: 1, column: 1, token: String::from("99999999999999999999") }));
+    assert_eq!(parse_program("\n# nothing\n"), Err(IntcodeError::ParseError { line: 1, column: 1, token: String::new() }));
+}
+
+#[test]
+fn test_binary_format() {
+    let program = vec![0, 1, -1, 63, -64, 64, 1 << 40, i64::MAX, i64::MIN, 99];
+    let bytes = encode_binary(&program);
+    assert_eq!(&bytes[12..17], &[0, 2, 1, 126, 127]);
+    assert_eq!(read_program(bytes.as_slice()), Ok(program));
+    assert_eq!(read_program("1,2,3".as_bytes()), Ok(vec![1, 2, 3]));
+    assert_eq!(read_program("1,x".as_bytes()), Err(LoadError::Parse(IntcodeError::ParseError { line: 1, column: 3, token: String::from("x") })));
+
+    assert_eq!(decode_binary(b"1,2"), Err(LoadError::InvalidBinary { offset: 0, message: String::from("missing header") }));
+    let mut truncated = encode_binary(&[1 << 40]);
+    truncated.pop();
+    assert_eq!(decode_binary(&truncated), Err(LoadError::InvalidBinary { offset: 12, message: String::from("value is truncated") }));
+    let mut trailing = encode_binary(&[1]);
+    trailing.push(0);
+    assert_eq!(decode_binary(&trailing), Err(LoadError::InvalidBinary { offset: 13, message: String::from("unexpected data after the last value") }));
+    let mut overflow = encode_binary(&[]);
+    overflow[4] = 1;
+    overflow.extend_from_slice(&[0xff; 10]);
+    assert_eq!(decode_binary(&overflow).unwrap_err(), LoadError::InvalidBinary { offset: 12, message: String::from("value does not fit in 64 bits") });
+}
@@ -0,0 +1,757 @@
+use crate::cache::DecodeCache;
+use crate::disassembler::get_mnemonic;
+use crate::error::IntcodeError;
+use crate::instructions::{Instruction, MAX_INSTRUCTION_LENGTH, op_code::{OpCode, OpCodeId}};
+use crate::io::{IntcodeIo, QueueIo};
+use crate::lexer;
+use crate::memory::Memory;
+use crate::registry::InstructionSet;
+use crate::snapshot::Snapshot;
+use crate::threaded::{Flow, ThreadedCode};
+use crate::trace::{NoTrace, PendingEvent, Tracer};
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Execution state of an intcode machine
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum RunState {
+    Running,
+    AwaitingInput,
+    Halted,
+    Faulted,
+}
+
+/// How a machine executes instructions
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum Backend {
+    /// Decodes each instruction once and dispatches it on every execution
+    #[default]
+    Interpreter,
+    /// Translates each instruction once into a closure with its operands resolved, see the
+    /// `threaded` module. Translating costs more than decoding, so this pays off for programs
+    /// that loop over the same code many times rather than for short runs
+    Threaded,
+}
+
+/// Why a call to `Intcode::run` returned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The program executed a halt instruction
+    Halted,
+    /// The program requested input that the I/O could not provide
+    AwaitingInput,
+    /// The program faulted
+    Faulted(IntcodeError),
+    /// The step limit was reached first, the machine can be run again to continue
+    StepLimit,
+}
+
+/// Summary of a call to `Intcode::run`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// Why the run stopped
+    pub reason: ExitReason,
+    /// Number of instructions executed during the run, including the final halt
+    pub steps: u64,
+    /// Instruction pointer when the run stopped, the address of the halt for a halted program
+    pub instruction_pointer: usize,
+    /// Every value the program output during the run
+    pub outputs: Vec<i64>,
+}
+
+/// I/O that passes values through to another I/O and records every output
+struct RecordingIo<'a> {
+    io: &'a mut dyn IntcodeIo,
+    outputs: Vec<i64>,
+}
+
+impl IntcodeIo for RecordingIo<'_> {
+    fn read(&mut self) -> Option<i64> {
+        self.io.read()
+    }
+
+    fn write(&mut self, value: i64) {
+        self.outputs.push(value);
+        self.io.write(value);
+    }
+}
+
+/// A resumable intcode virtual machine
+///
+/// The machine owns its memory and registers so that a program can be paused when it
+/// requests input that is not yet available, inspected, and then continued later.
+///
+/// # Examples
+/// ```
+/// let mut machine = intcode::machine::Intcode::new(vec![3, 0, 4, 0, 99]);
+/// assert_eq!(machine.run_until_blocked(), intcode::machine::RunState::AwaitingInput);
+/// machine.push_input(42);
+/// assert_eq!(machine.run_until_output(), Some(42));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Intcode {
+    memory: Memory,
+    instruction_pointer: usize,
+    relative_base: i64,
+    state: RunState,
+    error: Option<IntcodeError>,
+    io: QueueIo,
+    cache: DecodeCache,
+    instruction_set: Option<Arc<InstructionSet>>,
+    backend: Backend,
+    threaded: ThreadedCode,
+}
+
+impl Intcode {
+    /// Creates a new machine with the instruction pointer at the start of the program
+    ///
+    /// # Arguments
+    /// * `program` - The initial memory image
+    pub fn new(program: Vec<i64>) -> Intcode {
+        Intcode::with_memory(Memory::new(program))
+    }
+
+    /// Creates a new machine that runs on an existing memory, such as a paged memory
+    ///
+    /// # Arguments
+    /// * `memory` - The initial memory, with the program starting at address zero
+    pub fn with_memory(memory: Memory) -> Intcode {
+        Intcode {
+            memory,
+            instruction_pointer: 0,
+            relative_base: 0,
+            state: RunState::Running,
+            error: None,
+            io: QueueIo::default(),
+            cache: DecodeCache::default(),
+            instruction_set: None,
+            backend: Backend::Interpreter,
+            threaded: ThreadedCode::default(),
+        }
+    }
+
+    /// Decodes instructions with an instruction set instead of the standard operations
+    ///
+    /// # Arguments
+    /// * `instruction_set` - The operations to decode, shared so that many machines can use it
+    pub fn with_instruction_set(mut self, instruction_set: Arc<InstructionSet>) -> Intcode {
+        self.instruction_set = Some(instruction_set);
+        self.cache.clear();
+        self.threaded.clear();
+        self
+    }
+
+    /// Executes instructions with a backend other than the interpreter
+    ///
+    /// # Examples
+    /// ```
+    /// use intcode::machine::{Backend, Intcode};
+    /// let mut machine = Intcode::new(vec![1102, 6, 7, 0, 4, 0, 99]).with_backend(Backend::Threaded);
+    /// assert_eq!(machine.run_until_output(), Some(42));
+    /// ```
+    pub fn with_backend(mut self, backend: Backend) -> Intcode {
+        self.set_backend(backend);
+        self
+    }
+
+    /// Changes the backend, which may be done at any point of a run
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    /// Gets the backend that executes instructions
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Queues a value to be consumed by the next input instruction
+    ///
+    /// A machine that was waiting on input becomes runnable again
+    pub fn push_input(&mut self, value: i64) {
+        self.io.push_input(value);
+        if self.state == RunState::AwaitingInput {
+            self.state = RunState::Running;
+        }
+    }
+
+    /// Removes and returns the oldest output that has not been read yet
+    pub fn pop_output(&mut self) -> Option<i64> {
+        self.io.pop_output()
+    }
+
+    /// Removes and returns every output that has not been read yet
+    pub fn drain_outputs(&mut self) -> Vec<i64> {
+        self.io.drain_outputs()
+    }
+
+    /// Gets the current run state
+    pub fn state(&self) -> RunState {
+        self.state
+    }
+
+    /// Gets the error that moved the machine into `RunState::Faulted`
+    pub fn error(&self) -> Option<&IntcodeError> {
+        self.error.as_ref()
+    }
+
+    /// Gets the current instruction pointer
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    /// Gets the current relative base register
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    /// Gets a read only view of the machine memory
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Gets a mutable view of the machine memory
+    ///
+    /// ## Note
+    /// Drops every cached instruction decode since any cell may be changed
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        self.cache.clear();
+        self.threaded.clear();
+        &mut self.memory
+    }
+
+    /// Consumes the machine and returns its memory
+    pub fn into_memory(self) -> Memory {
+        self.memory
+    }
+
+    /// Captures the complete state of the machine, including queued inputs and unread outputs
+    ///
+    /// The memory is shared with the machine until either of them writes to it
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory.clone(),
+            instruction_pointer: self.instruction_pointer,
+            relative_base: self.relative_base,
+            state: self.state,
+            error: self.error.clone(),
+            io: self.io.clone(),
+        }
+    }
+
+    /// Returns the machine to the state captured by a snapshot
+    ///
+    /// ## Note
+    /// The machine keeps its instruction set and backend, which are not part of a snapshot
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let instruction_set = self.instruction_set.take();
+        let backend = self.backend;
+        *self = Intcode::from_snapshot(snapshot.clone());
+        self.instruction_set = instruction_set;
+        self.backend = backend;
+    }
+
+    /// Creates a new machine from a snapshot, such as one loaded from a file
+    pub fn from_snapshot(snapshot: Snapshot) -> Intcode {
+        Intcode {
+            memory: snapshot.memory,
+            instruction_pointer: snapshot.instruction_pointer,
+            relative_base: snapshot.relative_base,
+            state: snapshot.state,
+            error: snapshot.error,
+            io: snapshot.io,
+            cache: DecodeCache::default(),
+            instruction_set: None,
+            backend: Backend::Interpreter,
+            threaded: ThreadedCode::default(),
+        }
+    }
+
+    /// Creates an independent copy of the machine that continues from the current state
+    ///
+    /// ## Note
+    /// Memory is copy on write, so forking is cheap and both machines only pay for a copy
+    /// once they write to memory. The fork starts with empty decode caches and decodes the
+    /// code it executes again, so the cost of forking does not grow with the code that ran
+    ///
+    /// # Examples
+    /// ```
+    /// let mut machine = intcode::machine::Intcode::new(vec![3, 0, 4, 0, 99]);
+    /// let mut fork = machine.fork();
+    /// machine.push_input(1);
+    /// fork.push_input(2);
+    /// assert_eq!(machine.run_until_output(), Some(1));
+    /// assert_eq!(fork.run_until_output(), Some(2));
+    /// ```
+    pub fn fork(&self) -> Intcode {
+        Intcode {
+            memory: self.memory.clone(),
+            instruction_pointer: self.instruction_pointer,
+            relative_base: self.relative_base,
+            state: self.state,
+            error: self.error.clone(),
+            io: self.io.clone(),
+            cache: DecodeCache::default(),
+            instruction_set: self.instruction_set.clone(),
+            backend: self.backend,
+            threaded: ThreadedCode::default(),
+        }
+    }
+
+    /// Executes a single instruction using the machine's own input and output queues
+    ///
+    /// ## Note
+    /// An input instruction with no queued input leaves the instruction pointer where it is
+    /// and moves the machine into `RunState::AwaitingInput`
+    pub fn step(&mut self) -> RunState {
+        let mut io = std::mem::take(&mut self.io);
+        let state = self.step_with_io(&mut io);
+        self.io = io;
+        state
+    }
+
+    /// Executes a single instruction reading and writing through an external I/O
+    ///
+    /// # Arguments
+    /// * `io` - I/O used by input and output instructions instead of the internal queues
+    pub fn step_with_io(&mut self, io: &mut dyn IntcodeIo) -> RunState {
+        self.step_traced(io, &mut NoTrace)
+    }
+
+    /// Executes a single instruction and reports it to a tracer
+    ///
+    /// Instructions that block on input or fault are not reported
+    ///
+    /// # Arguments
+    /// * `io`     - I/O used by input and output instructions instead of the internal queues
+    /// * `tracer` - Receives the executed instruction, `NoTrace` compiles down to `step_with_io`
+    pub fn step_traced<T: Tracer>(&mut self, io: &mut dyn IntcodeIo, tracer: &mut T) -> RunState {
+        match self.state {
+            RunState::Halted | RunState::Faulted => return self.state,
+            RunState::Running | RunState::AwaitingInput => (),
+        }
+
+        let ip = self.instruction_pointer;
+        // Instructions that do not decode fault below, so they have no event
+        let pending = match T::ENABLED {
+            true => self.instruction(ip).ok().map(|op| {
+                PendingEvent::begin(&self.memory, ip, self.relative_base, &op, self.mnemonic(self.memory.get(ip) % 100))
+            }),
+            false => None
+        };
+        self.state = match self.execute(io) {
+            Ok(state) => {
+                if let Some(pending) = pending {
+                    tracer.trace(&pending.finish(&self.memory));
+                }
+                state
+            },
+            Err(IntcodeError::InputExhausted { .. }) => RunState::AwaitingInput,
+            Err(e) => {
+                self.error = Some(e.at(ip, self.memory.get(ip)));
+                RunState::Faulted
+            }
+        };
+        self.state
+    }
+
+    /// Runs the machine until it produces an output, halts, faults or blocks on input
+    ///
+    /// Returns the produced output if there was one
+    pub fn run_until_output(&mut self) -> Option<i64> {
+        while !self.io.has_output() {
+            if self.step() != RunState::Running {
+                break;
+            }
+        }
+        self.io.pop_output()
+    }
+
+    /// Runs the machine until it halts, faults or blocks waiting on input
+    ///
+    /// Outputs produced along the way are queued and can be read with `pop_output`
+    pub fn run_until_blocked(&mut self) -> RunState {
+        while self.step() == RunState::Running {}
+        self.state
+    }
+
+    /// Runs the machine against an external I/O until it halts, faults or runs out of input
+    ///
+    /// # Arguments
+    /// * `io` - I/O used by input and output instructions instead of the internal queues
+    pub fn run_with_io(&mut self, io: &mut dyn IntcodeIo) -> RunState {
+        while self.step_with_io(io) == RunState::Running {}
+        self.state
+    }
+
+    /// Runs the machine against an external I/O and reports how the run ended
+    ///
+    /// # Arguments
+    /// * `io`         - I/O used by input and output instructions instead of the internal queues
+    /// * `step_limit` - Maximum number of instructions to execute, to stop programs that never halt
+    ///
+    /// # Examples
+    /// ```
+    /// use intcode::{io::QueueIo, machine::{ExitReason, Intcode}};
+    /// let mut machine = Intcode::new(vec![104, 7, 1105, 1, 0]);
+    /// let outcome = machine.run(&mut QueueIo::default(), Some(5));
+    /// assert_eq!((outcome.reason, outcome.steps, outcome.outputs), (ExitReason::StepLimit, 5, vec![7, 7, 7]));
+    /// ```
+    pub fn run(&mut self, io: &mut dyn IntcodeIo, step_limit: Option<u64>) -> RunOutcome {
+        self.run_with_tracer(io, step_limit, &mut NoTrace)
+    }
+
+    /// Runs the machine like `run`, reporting every executed instruction to a tracer
+    ///
+    /// # Arguments
+    /// * `io`         - I/O used by input and output instructions instead of the internal queues
+    /// * `step_limit` - Maximum number of instructions to execute, to stop programs that never halt
+    /// * `tracer`     - Receives each executed instruction, such as a `WriteLog`
+    pub fn run_with_tracer<T: Tracer>(&mut self, io: &mut dyn IntcodeIo, step_limit: Option<u64>, tracer: &mut T) -> RunOutcome {
+        let mut io = RecordingIo { io, outputs: Vec::new() };
+        let mut steps = 0;
+        let reason = loop {
+            let runnable = matches!(self.state, RunState::Running | RunState::AwaitingInput);
+            if runnable && step_limit.is_some_and(|limit| steps >= limit) {
+                break ExitReason::StepLimit;
+            }
+            let was_halted = self.state == RunState::Halted;
+            match self.step_traced(&mut io, tracer) {
+                RunState::Running => steps += 1,
+                RunState::Halted => {
+                    steps += u64::from(!was_halted);
+                    break ExitReason::Halted;
+                },
+                RunState::AwaitingInput => break ExitReason::AwaitingInput,
+                RunState::Faulted => break ExitReason::Faulted(self.error.clone().expect("A faulted machine always has an error")),
+            }
+        };
+        RunOutcome { reason, steps, instruction_pointer: self.instruction_pointer, outputs: io.outputs }
+    }
+
+    /// Runs the machine against an external I/O, reporting every executed instruction to a tracer
+    ///
+    /// # Arguments
+    /// * `io`     - I/O used by input and output instructions instead of the internal queues
+    /// * `tracer` - Receives each executed instruction, such as a `Profiler` or `JsonLinesTracer`
+    pub fn run_traced<T: Tracer>(&mut self, io: &mut dyn IntcodeIo, tracer: &mut T) -> RunState {
+        while self.step_traced(io, tracer) == RunState::Running {}
+        self.state
+    }
+
+    /// Helper function to decode and apply the instruction at the instruction pointer
+    fn execute(&mut self, io: &mut dyn IntcodeIo) -> Result<RunState, IntcodeError> {
+        let ip = self.instruction_pointer;
+        // Leaves room for the longest instruction, so that the next address always fits in an i64
+        if ip > i64::MAX as usize - MAX_INSTRUCTION_LENGTH {
+            return Err(IntcodeError::AddressOutOfRange { ip: 0, word: 0, address: ip as i64 });
+        }
+        if self.backend == Backend::Threaded && !self.threaded.is_interpreted(ip) {
+            if self.threaded.get(ip).is_none() {
+                let op = self.instruction(ip)?;
+                self.threaded.translate(ip, &op, &self.words(ip));
+            }
+            if let Some(operation) = self.threaded.get(ip) {
+                return match operation(&mut self.memory, &mut self.relative_base, io)? {
+                    Flow::Halt => Ok(RunState::Halted),
+                    Flow::Next { ip, written } => {
+                        if let Some(address) = written {
+                            self.invalidate(address);
+                        }
+                        self.jump(ip)
+                    }
+                };
+            }
+        }
+
+        let op = self.instruction(ip)?;
+        if let Instruction::Complete(_) = op {
+            return Ok(RunState::Halted);
+        }
+
+        let written = match op.get_output() {
+            Some(output) => lexer::get_parameter_address(output, self.relative_base).ok(),
+            None => None
+        };
+        let next = op.apply(&mut self.memory, ip as i64, &mut self.relative_base, io)?;
+        match (written, &op) {
+            (Some(address), _) => self.invalidate(address),
+            // A custom operation that does not report its output may have written anywhere
+            (None, Instruction::Custom(_)) => {
+                self.cache.clear();
+                self.threaded.clear();
+            },
+            (None, _) => ()
+        }
+        self.jump(next)
+    }
+
+    /// Helper function to move the instruction pointer to the next instruction
+    fn jump(&mut self, next: i64) -> Result<RunState, IntcodeError> {
+        self.instruction_pointer = match usize::try_from(next) {
+            Ok(ip) => ip,
+            Err(_) => return Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address: next })
+        };
+        Ok(RunState::Running)
+    }
+
+    /// Helper function to drop every cached decode and translation that covers a written address
+    fn invalidate(&mut self, address: i64) {
+        self.cache.invalidate(address);
+        self.threaded.invalidate(address);
+    }
+
+    /// Helper function to get the instruction starting at an address from the cache, decoding
+    /// and caching it if needed
+    fn instruction(&mut self, address: usize) -> Result<Instruction, IntcodeError> {
+        match self.cache.get(address) {
+            Some(op) => Ok(op.clone()),
+            None => {
+                let op = self.decode(address)?;
+                self.cache.insert(address, op.clone());
+                Ok(op)
+            }
+        }
+    }
+
+    /// Helper function to get the name of an op code in the instruction set the machine decodes
+    fn mnemonic(&self, op_code: i64) -> Cow<'static, str> {
+        let name = match &self.instruction_set {
+            Some(set) => set.mnemonic(op_code),
+            None => OpCodeId::from_i64(op_code).map(|id| Cow::Borrowed(get_mnemonic(id).0))
+        };
+        name.unwrap_or(Cow::Borrowed("?"))
+    }
+
+    /// Helper function to read the words of the instruction starting at an address
+    fn words(&self, address: usize) -> [i64; MAX_INSTRUCTION_LENGTH] {
+        std::array::from_fn(|i| self.memory.get(address + i))
+    }
+
+    /// Helper function to decode the instruction starting at an address
+    ///
+    /// Negative words and op codes without an operation are unknown op codes, any other word
+    /// that does not parse has a mode digit that is not a parameter mode
+    fn decode(&self, address: usize) -> Result<Instruction, IntcodeError> {
+        let words = self.words(address);
+        let op_code = words[0] % 100;
+        let known = match &self.instruction_set {
+            Some(set) => set.name(op_code).is_some(),
+            None => OpCodeId::from_i64(op_code).is_some()
+        };
+        if words[0] < 0 || !known {
+            return Err(IntcodeError::UnknownOpcode { ip: 0, word: 0 });
+        }
+        if lexer::parse_instruction_type(words[0]).is_none() {
+            return Err(IntcodeError::InvalidParameterMode { ip: 0, word: 0 });
+        }
+        let op = match &self.instruction_set {
+            Some(set) => set.decode(&words),
+            None => Instruction::parse_from_slice(&words)
+        };
+        match op {
+            Some(op) => Ok(op),
+            None => Err(IntcodeError::UnknownOpcode { ip: 0, word: 0 })
+        }
+    }
+}
+
+#[test]
+fn test_run_until_blocked_suspends_on_input() {
+    let mut machine = Intcode::new(vec![3, 0, 4, 0, 99]);
+    assert_eq!(machine.run_until_blocked(), RunState::AwaitingInput);
+    assert_eq!(machine.instruction_pointer(), 0);
+
+    machine.push_input(7);
+    assert_eq!(machine.state(), RunState::Running);
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.drain_outputs(), vec![7]);
+}
+
+#[test]
+fn test_run_until_output() {
+    // Outputs 1 if the input is equal to 8, otherwise 0
+    let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+    let mut machine = Intcode::new(program.clone());
+    machine.push_input(8);
+    assert_eq!(machine.run_until_output(), Some(1));
+    assert_eq!(machine.run_until_output(), None);
+    assert_eq!(machine.state(), RunState::Halted);
+
+    let mut machine = Intcode::new(program);
+    machine.push_input(5);
+    assert_eq!(machine.run_until_output(), Some(0));
+}
+
+#[test]
+fn test_step() {
+    let mut machine = Intcode::new(vec![1002, 4, 3, 4, 33]);
+    assert_eq!(machine.step(), RunState::Running);
+    assert_eq!(machine.instruction_pointer(), 4);
+    assert_eq!(machine.memory().to_vec(), Ok(vec![1002, 4, 3, 4, 99]));
+    assert_eq!(machine.step(), RunState::Halted);
+    assert_eq!(machine.step(), RunState::Halted);
+}
+
+#[test]
+fn test_run_with_io() {
+    let mut machine = Intcode::new(vec![3, 0, 4, 0, 3, 0, 4, 0, 99]);
+    let mut io = crate::io::IterIo::new(vec![4, 5]);
+    assert_eq!(machine.run_with_io(&mut io), RunState::Halted);
+    assert_eq!(io.outputs(), &[4, 5]);
+}
+
+#[test]
+fn test_relative_base() {
+    let mut machine = Intcode::new(vec![109, 5, 204, 7, 21101, 3, 4, 2, 99, 0, 0, 0, 77]);
+    assert_eq!(machine.run_until_output(), Some(77));
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.relative_base(), 5);
+    assert_eq!(machine.memory().read(7), Ok(7));
+
+    let mut machine = Intcode::new(vec![104, 1125899906842624, 99]);
+    assert_eq!(machine.run_until_output(), Some(1125899906842624));
+
+    let mut machine = Intcode::new(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0]);
+    assert_eq!(machine.run_until_output(), Some(1219070632396864));
+}
+
+#[test]
+fn test_memory_beyond_program() {
+    // Quine that relies on reading and writing past the end of the program
+    let program = vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+    let mut machine = Intcode::new(program.clone());
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.drain_outputs(), program);
+
+    let mut machine = Intcode::with_memory(Memory::paged(&[1101, 2, 3, 1 << 40, 4, 1 << 40, 99]));
+    assert_eq!(machine.run_until_output(), Some(5));
+}
+
+#[test]
+fn test_faults() {
+    let mut machine = Intcode::new(vec![1101, 20, 22, 4, 0]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::UnknownOpcode { ip: 4, word: 42 }));
+
+    let mut machine = Intcode::new(vec![30001, 0, 0, 0]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::InvalidParameterMode { ip: 0, word: 30001 }));
+
+    let mut machine = Intcode::new(vec![11101, 0, 0, 0]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::WriteToImmediate { ip: 0, word: 11101 }));
+
+    let mut machine = Intcode::new(vec![204, -1]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::NegativeAddress { ip: 0, word: 204, address: -1 }));
+
+    let mut machine = Intcode::new(vec![1105, 1, -3]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::NegativeAddress { ip: 0, word: 1105, address: -3 }));
+
+    let mut machine = Intcode::new(vec![1101, 0, 0, 1 << 40, 99]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::AddressOutOfRange { ip: 0, word: 1101, address: 1 << 40 }));
+    assert_eq!(machine.step(), RunState::Faulted);
+}
+
+#[test]
+fn test_self_modifying_code() {
+    // Overwrites the output at address zero with a halt and jumps back to it
+    let mut machine = Intcode::new(vec![104, 7, 1101, 0, 99, 0, 1105, 1, 0]);
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.drain_outputs(), vec![7]);
+
+    let mut machine = Intcode::new(vec![104, 7, 1105, 1, 0]);
+    machine.step();
+    machine.step();
+    machine.memory_mut().write(0, 99).unwrap();
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.drain_outputs(), vec![7]);
+}
+
+#[test]
+fn test_snapshot_and_restore() {
+    // Adds its first input to its second and outputs the result
+    let mut machine = Intcode::new(vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99, 0, 0]);
+    machine.push_input(40);
+    assert_eq!(machine.run_until_blocked(), RunState::AwaitingInput);
+    let checkpoint = machine.snapshot();
+
+    machine.push_input(2);
+    assert_eq!(machine.run_until_output(), Some(42));
+    assert_eq!(machine.state(), RunState::Running);
+
+    machine.restore(&checkpoint);
+    assert_eq!(machine.state(), RunState::AwaitingInput);
+    assert_eq!(machine.instruction_pointer(), 2);
+    machine.push_input(-40);
+    assert_eq!(machine.run_until_output(), Some(0));
+
+    let mut branches = (0..3).map(|x| {
+        let mut fork = Intcode::from_snapshot(checkpoint.clone());
+        fork.push_input(x);
+        fork
+    }).collect::<Vec<Intcode>>();
+    let outputs = branches.iter_mut().map(|m| m.run_until_output()).collect::<Vec<Option<i64>>>();
+    assert_eq!(outputs, vec![Some(40), Some(41), Some(42)]);
+}
+
+#[test]
+fn test_fork_leaves_decode_caches_behind() {
+    // Outputs each of a thousand distinct constants, so every instruction is decoded once
+    let mut program = (0..1000).flat_map(|x| [104, x]).collect::<Vec<i64>>();
+    program.extend_from_slice(&[3, 0, 4, 0, 99]);
+    for backend in [Backend::Interpreter, Backend::Threaded] {
+        let mut machine = Intcode::new(program.clone()).with_backend(backend);
+        assert_eq!(machine.run_until_blocked(), RunState::AwaitingInput);
+        assert!(machine.cache.get(0).is_some() || machine.threaded.get(0).is_some());
+
+        let mut fork = machine.fork();
+        assert!(fork.cache.get(0).is_none() && fork.threaded.get(0).is_none());
+        assert_eq!(fork.drain_outputs(), machine.drain_outputs());
+        fork.push_input(7);
+        machine.push_input(8);
+        assert_eq!(fork.run_until_output(), Some(7));
+        assert_eq!(machine.run_until_output(), Some(8));
+        assert_eq!(fork.backend(), backend);
+    }
+}
+
+#[test]
+fn test_run_outcome() {
+    let mut machine = Intcode::new(vec![3, 0, 4, 0, 99]);
+    let outcome = machine.run(&mut QueueIo::new(vec![6]), None);
+    assert_eq!(outcome, RunOutcome { reason: ExitReason::Halted, steps: 3, instruction_pointer: 4, outputs: vec![6] });
+    assert_eq!(machine.run(&mut QueueIo::default(), None).steps, 0);
+
+    let outcome = Intcode::new(vec![3, 0, 4, 0, 99]).run(&mut QueueIo::default(), None);
+    assert_eq!((outcome.reason, outcome.steps, outcome.instruction_pointer), (ExitReason::AwaitingInput, 0, 0));
+
+    let outcome = Intcode::new(vec![104, 1, 42]).run(&mut QueueIo::default(), Some(10));
+    assert_eq!(outcome.reason, ExitReason::Faulted(IntcodeError::UnknownOpcode { ip: 2, word: 42 }));
+    assert_eq!((outcome.steps, outcome.outputs), (1, vec![1]));
+
+    // A program that loops forever is stopped by the step limit and can be continued
+    let mut machine = Intcode::new(vec![1105, 1, 0]);
+    let outcome = machine.run(&mut QueueIo::default(), Some(1000));
+    assert_eq!((outcome.reason, outcome.steps), (ExitReason::StepLimit, 1000));
+    assert_eq!(machine.state(), RunState::Running);
+    machine.memory_mut().write(0, 99).unwrap();
+    assert_eq!(machine.run(&mut QueueIo::default(), Some(1000)).reason, ExitReason::Halted);
+}
+
+#[test]
+fn test_run_with_tracer() {
+    use crate::trace::WriteLog;
+
+    // Counts address 9 up forever, the step limit stops it after three increments
+    let mut machine = Intcode::new(vec![1001, 9, 1, 9, 1105, 1, 0, 99, 0, 0]).with_backend(Backend::Threaded);
+    let mut log = WriteLog::default();
+    let outcome = machine.run_with_tracer(&mut QueueIo::default(), Some(6), &mut log);
+    assert_eq!((outcome.reason, outcome.steps), (ExitReason::StepLimit, 6));
+    assert_eq!(log.writes().iter().map(|w| (w.step, w.write.new)).collect::<Vec<_>>(), vec![(0, 1), (2, 2), (4, 3)]);
+    assert_eq!(machine.memory().get(9), 3);
+}
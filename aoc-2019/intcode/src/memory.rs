@@ -0,0 +1,378 @@
+use crate::error::IntcodeError;
+use crate::trace::MemoryWrite;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// Number of cells in each page of a paged memory
+pub const PAGE_SIZE: usize = 1024;
+
+/// Highest address a dense memory can grow to, paged memories have no limit
+pub const MAX_DENSE_ADDRESS: i64 = (1 << 24) - 1;
+
+/// Program memory that grows on write and reads zero from addresses that were never written
+///
+/// ## Note
+/// Cloning a memory is cheap, the cells are shared between the clones and only copied when
+/// one of them is written to. Dense memories copy the whole image, paged memories a single page
+///
+/// # Examples
+/// ```
+/// let mut memory = intcode::memory::Memory::new(vec![1, 2, 3]);
+/// assert_eq!(memory.read(10), Ok(0));
+/// memory.write(10, 5).unwrap();
+/// assert_eq!(memory.len(), 11);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "MemoryImage", try_from = "MemoryImage")]
+pub enum Memory {
+    /// Contiguous storage that is resized to fit the highest written address
+    Dense(Arc<Vec<i64>>),
+    /// Fixed size pages that are only allocated once something is written to them
+    Paged(PagedMemory),
+}
+
+/// Sparse page based storage for programs that write to very high addresses
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PagedMemory {
+    pages: HashMap<usize, Arc<[i64; PAGE_SIZE]>>,
+    len: usize,
+}
+
+/// Serialized form of a memory, pages are stored as plain lists since arrays of `PAGE_SIZE`
+/// cells are not supported by serde
+#[derive(Serialize, Deserialize)]
+enum MemoryImage {
+    Dense(Vec<i64>),
+    Paged { len: usize, pages: Vec<(usize, Vec<i64>)> },
+}
+
+impl Memory {
+    /// Creates a new contiguous memory initialised with a program
+    pub fn new(program: Vec<i64>) -> Memory {
+        Memory::Dense(Arc::new(program))
+    }
+
+    /// Creates a new sparse paged memory initialised with a program
+    pub fn paged(program: &[i64]) -> Memory {
+        let mut paged = PagedMemory::default();
+        for (address, value) in program.iter().enumerate() {
+            paged.write(address, *value);
+        }
+        Memory::Paged(paged)
+    }
+
+    /// Reads the value stored at an address, addresses that were never written read as zero
+    ///
+    /// Returns `IntcodeError::NegativeAddress` if the address is negative
+    pub fn read(&self, address: i64) -> Result<i64, IntcodeError> {
+        match usize::try_from(address) {
+            Ok(index) => Ok(self.get(index)),
+            Err(_) => Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address })
+        }
+    }
+
+    /// Writes a value to an address, growing the memory as needed
+    ///
+    /// Returns `IntcodeError::NegativeAddress` if the address is negative and
+    /// `IntcodeError::AddressOutOfRange` if a dense memory would grow past `MAX_DENSE_ADDRESS`
+    pub fn write(&mut self, address: i64, value: i64) -> Result<(), IntcodeError> {
+        let index = match usize::try_from(address) {
+            Ok(index) => index,
+            Err(_) => return Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address })
+        };
+        match self {
+            Memory::Dense(_) if address > MAX_DENSE_ADDRESS => {
+                return Err(IntcodeError::AddressOutOfRange { ip: 0, word: 0, address })
+            },
+            Memory::Dense(cells) => {
+                let cells = Arc::make_mut(cells);
+                if index >= cells.len() {
+                    cells.resize(index + 1, 0);
+                }
+                cells[index] = value;
+            },
+            Memory::Paged(paged) => paged.write(index, value),
+        }
+        Ok(())
+    }
+
+    /// Gets the value stored at an index, indices that were never written read as zero
+    pub fn get(&self, index: usize) -> i64 {
+        match self {
+            Memory::Dense(cells) => cells.get(index).copied().unwrap_or(0),
+            Memory::Paged(paged) => paged.pages.get(&(index / PAGE_SIZE))
+                                               .map_or(0, |page| page[index % PAGE_SIZE]),
+        }
+    }
+
+    /// Gets one past the highest address that has been written to or loaded
+    pub fn len(&self) -> usize {
+        match self {
+            Memory::Dense(cells) => cells.len(),
+            Memory::Paged(paged) => paged.len,
+        }
+    }
+
+    /// Checks if nothing has been written to or loaded into the memory
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads a run of consecutive cells starting at an address
+    ///
+    /// # Arguments
+    /// * `address` - The first address to read
+    /// * `count`   - The number of cells to read
+    pub fn read_range(&self, address: usize, count: usize) -> Vec<i64> {
+        (address..address.saturating_add(count)).map(|index| self.get(index)).collect()
+    }
+
+    /// Copies the memory into a contiguous vector of `len()` cells
+    ///
+    /// Returns `IntcodeError::AddressOutOfRange` with the highest address if a paged memory
+    /// holds more cells than a dense memory can, instead of allocating every cell up to it
+    pub fn to_vec(&self) -> Result<Vec<i64>, IntcodeError> {
+        match self {
+            Memory::Dense(cells) => Ok(cells.to_vec()),
+            Memory::Paged(paged) if paged.len > MAX_DENSE_ADDRESS as usize + 1 => {
+                Err(IntcodeError::AddressOutOfRange { ip: 0, word: 0, address: (paged.len - 1) as i64 })
+            },
+            Memory::Paged(_) => Ok(self.read_range(0, self.len())),
+        }
+    }
+
+    /// Consumes the memory and returns it as a contiguous vector of `len()` cells
+    ///
+    /// Returns `IntcodeError::AddressOutOfRange` like `to_vec`
+    pub fn into_vec(self) -> Result<Vec<i64>, IntcodeError> {
+        match self {
+            Memory::Dense(cells) => Ok(Arc::try_unwrap(cells).unwrap_or_else(|shared| shared.to_vec())),
+            Memory::Paged(_) => self.to_vec(),
+        }
+    }
+
+    /// Formats a run of cells as rows of decimal values, each row starting with its address
+    ///
+    /// Cells past the end of the memory are not shown, so `count` may be larger than `len()`
+    ///
+    /// # Arguments
+    /// * `address` - The first address to show
+    /// * `count`   - The number of cells to show
+    /// * `columns` - The number of cells on each row
+    ///
+    /// # Examples
+    /// ```
+    /// let memory = intcode::memory::Memory::new(vec![109, 1, 204, -1, 1001, 100]);
+    /// assert_eq!(memory.dump(0, 6, 4), "\
+    /// 0000:  109    1  204   -1
+    /// 0004: 1001  100
+    /// ");
+    /// ```
+    pub fn dump(&self, address: usize, count: usize, columns: usize) -> String {
+        let columns = columns.max(1);
+        let cells = self.read_range(address, count.min(self.len().saturating_sub(address)));
+        let width = cells.iter().map(|value| value.to_string().len()).max().unwrap_or(1);
+        let mut text = String::new();
+        for (row, chunk) in cells.chunks(columns).enumerate() {
+            let values = chunk.iter().map(|value| format!("{:>width$}", value)).collect::<Vec<String>>().join(" ");
+            text.push_str(&format!("{:04}: {}\n", address + row * columns, values));
+        }
+        text
+    }
+
+    /// Compares every cell with another memory, such as the memory of a diverging run
+    ///
+    /// Returns the cells that differ in address order, with `old` read from this memory and
+    /// `new` from the other one. Pages shared between clones are skipped without comparing them
+    ///
+    /// # Examples
+    /// ```
+    /// use intcode::{memory::Memory, trace::MemoryWrite};
+    /// let before = Memory::new(vec![1, 2, 3]);
+    /// let after = Memory::new(vec![1, 5, 3, 0, 7]);
+    /// assert_eq!(before.diff(&after), vec![MemoryWrite { address: 1, old: 2, new: 5 }, MemoryWrite { address: 4, old: 0, new: 7 }]);
+    /// ```
+    pub fn diff(&self, other: &Memory) -> Vec<MemoryWrite> {
+        let pages = self.page_indices().into_iter().chain(other.page_indices()).collect::<BTreeSet<usize>>();
+        let mut changes = Vec::new();
+        for index in pages {
+            let (old, new) = (self.page(index), other.page(index));
+            if let (Some(old), Some(new)) = (old, new) {
+                if std::ptr::eq(old, new) {
+                    continue;
+                }
+            }
+            let cell = |page: Option<&[i64]>, offset: usize| page.and_then(|p| p.get(offset)).copied().unwrap_or(0);
+            for offset in 0..PAGE_SIZE {
+                let (a, b) = (cell(old, offset), cell(new, offset));
+                if a != b {
+                    changes.push(MemoryWrite { address: (index * PAGE_SIZE + offset) as i64, old: a, new: b });
+                }
+            }
+        }
+        changes
+    }
+
+    /// Helper function to get the indices of the `PAGE_SIZE` blocks that may hold values
+    fn page_indices(&self) -> Vec<usize> {
+        match self {
+            Memory::Dense(cells) => (0..cells.len().div_ceil(PAGE_SIZE)).collect(),
+            Memory::Paged(paged) => paged.pages.keys().copied().collect(),
+        }
+    }
+
+    /// Helper function to get the cells of a `PAGE_SIZE` block, cut short at the end of a dense memory
+    fn page(&self, index: usize) -> Option<&[i64]> {
+        match self {
+            Memory::Dense(cells) => {
+                let start = index * PAGE_SIZE;
+                (start < cells.len()).then(|| &cells[start..(start + PAGE_SIZE).min(cells.len())])
+            },
+            Memory::Paged(paged) => paged.pages.get(&index).map(|page| &page[..]),
+        }
+    }
+}
+
+impl PagedMemory {
+    /// Helper function to write to a page, allocating it if needed
+    fn write(&mut self, index: usize, value: i64) {
+        let page = self.pages.entry(index / PAGE_SIZE).or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+        Arc::make_mut(page)[index % PAGE_SIZE] = value;
+        self.len = self.len.max(index + 1);
+    }
+}
+
+impl From<Vec<i64>> for Memory {
+    fn from(program: Vec<i64>) -> Self {
+        Memory::new(program)
+    }
+}
+
+impl From<Memory> for MemoryImage {
+    fn from(memory: Memory) -> Self {
+        match memory {
+            Memory::Dense(cells) => MemoryImage::Dense(Arc::try_unwrap(cells).unwrap_or_else(|shared| shared.to_vec())),
+            Memory::Paged(paged) => {
+                let mut pages = paged.pages.iter().map(|(index, page)| (*index, page.to_vec())).collect::<Vec<(usize, Vec<i64>)>>();
+                pages.sort_unstable_by_key(|(index, _)| *index);
+                MemoryImage::Paged { len: paged.len, pages }
+            }
+        }
+    }
+}
+
+impl TryFrom<MemoryImage> for Memory {
+    type Error = String;
+
+    fn try_from(image: MemoryImage) -> Result<Self, Self::Error> {
+        match image {
+            MemoryImage::Dense(cells) => Ok(Memory::new(cells)),
+            MemoryImage::Paged { len, pages } => {
+                let mut paged = PagedMemory { pages: HashMap::new(), len };
+                for (index, cells) in pages {
+                    let page = <[i64; PAGE_SIZE]>::try_from(cells)
+                        .map_err(|cells| format!("page {} has {} cells instead of {}", index, cells.len(), PAGE_SIZE))?;
+                    paged.pages.insert(index, Arc::new(page));
+                }
+                Ok(Memory::Paged(paged))
+            }
+        }
+    }
+}
+
+
+#[test]
+fn test_dense_memory_grows_on_write() {
+    let mut memory = Memory::new(vec![1, 2, 3]);
+    assert_eq!(memory.read(1), Ok(2));
+    assert_eq!(memory.read(100), Ok(0));
+    assert_eq!(memory.len(), 3);
+
+    memory.write(5, 7).unwrap();
+    assert_eq!(memory.len(), 6);
+    assert_eq!(memory.into_vec(), Ok(vec![1, 2, 3, 0, 0, 7]));
+}
+
+#[test]
+fn test_paged_memory() {
+    let mut memory = Memory::paged(&[1, 2, 3]);
+    assert_eq!(memory.read(2), Ok(3));
+    assert_eq!(memory.read(1 << 40), Ok(0));
+
+    memory.write(1 << 40, 9).unwrap();
+    assert_eq!(memory.read(1 << 40), Ok(9));
+    assert_eq!(memory.len(), (1 << 40) + 1);
+    if let Memory::Paged(paged) = &memory {
+        assert_eq!(paged.pages.len(), 2);
+    }
+    assert_eq!(memory.to_vec(), Err(IntcodeError::AddressOutOfRange { ip: 0, word: 0, address: 1 << 40 }));
+    memory.write(i64::MAX, 1).unwrap();
+    assert_eq!(memory.into_vec(), Err(IntcodeError::AddressOutOfRange { ip: 0, word: 0, address: i64::MAX }));
+
+    let mut memory = Memory::paged(&[1]);
+    memory.write(MAX_DENSE_ADDRESS, 2).unwrap();
+    assert_eq!(memory.to_vec().map(|cells| cells.len()), Ok(MAX_DENSE_ADDRESS as usize + 1));
+}
+
+#[test]
+fn test_read_range() {
+    let memory = Memory::paged(&[4, 5, 6]);
+    assert_eq!(memory.read_range(1, 4), vec![5, 6, 0, 0]);
+    assert_eq!(memory.to_vec(), Ok(vec![4, 5, 6]));
+}
+
+#[test]
+fn test_invalid_addresses() {
+    let mut memory = Memory::new(vec![1]);
+    assert_eq!(memory.read(-1), Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address: -1 }));
+    assert_eq!(memory.write(-1, 0), Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address: -1 }));
+    assert_eq!(memory.write(1 << 40, 0), Err(IntcodeError::AddressOutOfRange { ip: 0, word: 0, address: 1 << 40 }));
+    assert_eq!(memory.len(), 1);
+}
+
+#[test]
+fn test_clones_share_cells_until_written() {
+    let memory = Memory::new(vec![1, 2, 3]);
+    let mut copy = memory.clone();
+    if let (Memory::Dense(a), Memory::Dense(b)) = (&memory, &copy) {
+        assert!(Arc::ptr_eq(a, b));
+    }
+    copy.write(0, 9).unwrap();
+    assert_eq!(memory.to_vec(), Ok(vec![1, 2, 3]));
+    assert_eq!(copy.to_vec(), Ok(vec![9, 2, 3]));
+
+    let memory = Memory::paged(&[1, 2, 3]);
+    let mut copy = memory.clone();
+    copy.write(1 << 40, 9).unwrap();
+    assert_eq!(memory.read(1 << 40), Ok(0));
+    if let (Memory::Paged(a), Memory::Paged(b)) = (&memory, &copy) {
+        assert!(Arc::ptr_eq(&a.pages[&0], &b.pages[&0]));
+    }
+}
+
+#[test]
+fn test_diff() {
+    let memory = Memory::new(vec![1, 2, 3]);
+    let mut copy = memory.clone();
+    assert_eq!(memory.diff(&copy), Vec::new());
+    copy.write(2, 9).unwrap();
+    copy.write(PAGE_SIZE as i64 + 1, 4).unwrap();
+    assert_eq!(memory.diff(&copy), vec![
+        MemoryWrite { address: 2, old: 3, new: 9 },
+        MemoryWrite { address: PAGE_SIZE as i64 + 1, old: 0, new: 4 },
+    ]);
+
+    let mut paged = Memory::paged(&[1, 2, 3]);
+    paged.write(1 << 40, 5).unwrap();
+    assert_eq!(paged.diff(&memory), vec![MemoryWrite { address: 1 << 40, old: 5, new: 0 }]);
+}
+
+#[test]
+fn test_dump() {
+    let memory = Memory::paged(&[3, -20, 4]);
+    assert_eq!(memory.dump(1, 5, 2), "0001: -20   4\n");
+    assert_eq!(memory.dump(0, usize::MAX, 2), "0000:   3 -20\n0002:   4\n");
+    assert_eq!(memory.dump(4, 2, 8), "");
+    assert_eq!(memory.dump(0, 0, 8), "");
+}
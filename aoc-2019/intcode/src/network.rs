@@ -0,0 +1,217 @@
+use crate::error::IntcodeError;
+use crate::machine::{Intcode, RunState};
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::{stream, FutureExt, Sink, SinkExt, Stream, StreamExt};
+use tokio::task::JoinHandle;
+
+/// Number of instructions a machine executes between yields to the scheduler when it is
+/// neither waiting on input nor producing output
+const YIELD_INTERVAL: usize = 10_000;
+
+/// What an asynchronous machine does when it needs input that has not arrived yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputPolicy {
+    /// Suspend the task until the input stream produces a value
+    Wait,
+    /// Feed the given value straight away and let other tasks run, such as -1 for a network
+    /// interface controller with an empty packet queue
+    NonBlocking(i64),
+}
+
+/// A packet of two values sent to the machine at a network address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub address: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Runs a machine as an asynchronous task until it halts
+///
+/// # Arguments
+/// * `machine` - The machine to run, any inputs already queued on it are read first
+/// * `input`   - Stream of values for the input instructions
+/// * `output`  - Sink that every output value is sent to
+/// * `policy`  - What to do when the machine needs input that is not available yet
+///
+/// Returns the halted machine, or an error if it faults, the input stream ends while waiting
+/// on it or the output sink is closed
+///
+/// # Examples
+/// ```
+/// use futures::{channel::mpsc, stream, StreamExt};
+/// use intcode::{machine::Intcode, network::{run_async, InputPolicy}};
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let (sender, receiver) = mpsc::unbounded();
+/// run_async(Intcode::new(vec![3, 0, 4, 0, 99]), stream::iter([7]), sender, InputPolicy::Wait).await.unwrap();
+/// assert_eq!(receiver.collect::<Vec<i64>>().await, vec![7]);
+/// # });
+/// ```
+pub async fn run_async<S, K>(mut machine: Intcode, mut input: S, mut output: K, policy: InputPolicy) -> Result<Intcode, IntcodeError>
+where
+    S: Stream<Item = i64> + Unpin,
+    K: Sink<i64> + Unpin,
+{
+    let mut steps = 0;
+    loop {
+        let state = machine.step();
+        while let Some(value) = machine.pop_output() {
+            if output.send(value).await.is_err() {
+                return Err(IntcodeError::Io(String::from("output sink was closed")));
+            }
+        }
+
+        match state {
+            RunState::Running => {
+                steps += 1;
+                if steps % YIELD_INTERVAL == 0 {
+                    tokio::task::yield_now().await;
+                }
+            },
+            RunState::AwaitingInput => match policy {
+                InputPolicy::Wait => match input.next().await {
+                    Some(value) => machine.push_input(value),
+                    None => {
+                        let ip = machine.instruction_pointer();
+                        return Err(IntcodeError::InputExhausted { ip, word: machine.memory().get(ip) });
+                    }
+                },
+                InputPolicy::NonBlocking(default) => match input.next().now_or_never().flatten() {
+                    Some(value) => machine.push_input(value),
+                    None => {
+                        machine.push_input(default);
+                        tokio::task::yield_now().await;
+                    }
+                },
+            },
+            RunState::Halted => return Ok(machine),
+            RunState::Faulted => return Err(machine.error().cloned().expect("A faulted machine always has an error")),
+        }
+    }
+}
+
+/// Delivers packets to the machines of a network, or to the outside for unknown addresses
+#[derive(Debug, Clone)]
+pub struct Router {
+    machines: Vec<UnboundedSender<Packet>>,
+    external: UnboundedSender<Packet>,
+}
+
+impl Router {
+    /// Sends a packet to the machine at its address
+    ///
+    /// Packets for addresses outside the network are passed to `Network::next_packet`
+    pub fn deliver(&self, packet: Packet) {
+        let sender = match usize::try_from(packet.address).ok().and_then(|a| self.machines.get(a)) {
+            Some(sender) => sender,
+            None => &self.external
+        };
+        // A closed channel means its machine or the network has stopped, so the packet is dropped
+        let _ = sender.unbounded_send(packet);
+    }
+}
+
+/// A set of machines running the same program as tasks that exchange packets
+///
+/// Each machine first reads its network address, then reads packets as an `x` and `y` pair,
+/// reading -1 whenever its queue is empty. Every three values a machine outputs are sent as
+/// a packet of destination address, `x` and `y`
+///
+/// ## Note
+/// Must be created from within a tokio runtime
+#[derive(Debug)]
+pub struct Network {
+    router: Router,
+    packets: UnboundedReceiver<Packet>,
+    tasks: Vec<JoinHandle<Result<Intcode, IntcodeError>>>,
+}
+
+impl Network {
+    /// Starts one machine per network address
+    ///
+    /// # Arguments
+    /// * `program` - The program that every machine runs
+    /// * `size`    - The number of machines, with addresses starting from zero
+    pub fn spawn(program: &[i64], size: usize) -> Network {
+        let (external, packets) = mpsc::unbounded();
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..size).map(|_| mpsc::unbounded::<Packet>()).unzip();
+        let router = Router { machines: senders, external };
+
+        let tasks = receivers.into_iter().enumerate().map(|(address, receiver)| {
+            let input = stream::iter([address as i64])
+                .chain(receiver.flat_map(|packet| stream::iter([packet.x, packet.y])));
+            let (output, outbox) = mpsc::unbounded::<i64>();
+            tokio::spawn(Network::forward(outbox, router.clone()));
+            let machine = Intcode::new(program.to_vec());
+            tokio::spawn(run_async(machine, input, output, InputPolicy::NonBlocking(-1)))
+        }).collect();
+        Network { router, packets, tasks }
+    }
+
+    /// Gets the router so that packets can be injected into the network
+    pub fn router(&self) -> &Router {
+        &self.router
+    }
+
+    /// Waits for the next packet sent to an address outside the network
+    pub async fn next_packet(&mut self) -> Option<Packet> {
+        self.packets.next().await
+    }
+
+    /// Stops every machine in the network
+    pub fn shutdown(self) {
+        self.tasks.iter().for_each(|task| task.abort());
+    }
+
+    /// Helper function to group the outputs of a machine into packets and route them
+    async fn forward(mut outbox: UnboundedReceiver<i64>, router: Router) {
+        while let (Some(address), Some(x), Some(y)) = (outbox.next().await, outbox.next().await, outbox.next().await) {
+            router.deliver(Packet { address, x, y });
+        }
+    }
+}
+
+
+#[tokio::test]
+async fn test_run_async_input_policies() {
+    let (sender, receiver) = mpsc::unbounded();
+    let machine = run_async(Intcode::new(vec![3, 0, 4, 0, 3, 0, 4, 0, 99]), stream::iter([1, 2]), sender, InputPolicy::Wait).await.unwrap();
+    assert_eq!(machine.state(), RunState::Halted);
+    assert_eq!(receiver.collect::<Vec<i64>>().await, vec![1, 2]);
+
+    let (sender, receiver) = mpsc::unbounded();
+    let (_input, pending) = mpsc::unbounded::<i64>();
+    run_async(Intcode::new(vec![3, 0, 4, 0, 99]), pending, sender, InputPolicy::NonBlocking(-1)).await.unwrap();
+    assert_eq!(receiver.collect::<Vec<i64>>().await, vec![-1]);
+
+    let (sender, _receiver) = mpsc::unbounded();
+    let result = run_async(Intcode::new(vec![3, 0, 99]), stream::empty(), sender, InputPolicy::Wait).await;
+    assert_eq!(result.unwrap_err(), IntcodeError::InputExhausted { ip: 0, word: 3 });
+}
+
+#[tokio::test]
+async fn test_network_routes_packets() {
+    // Waits for a packet and sends it to address 255 with its own address added to x
+    let program = crate::assembler::assemble("
+                in -> [addr]
+        wait:   in -> [x]
+                eq [x], #-1, [empty]
+                jt [empty], #wait
+                in -> [y]
+                add [x], [addr], [x]
+                out #255
+                out [x]
+                out [y]
+                hlt
+        addr:   data 0
+        x:      data 0
+        y:      data 0
+        empty:  data 0
+    ").unwrap();
+    let mut network = Network::spawn(&program, 3);
+    network.router().deliver(Packet { address: 2, x: 5, y: 6 });
+    assert_eq!(network.next_packet().await, Some(Packet { address: 255, x: 7, y: 6 }));
+    network.router().deliver(Packet { address: 0, x: 1, y: 2 });
+    assert_eq!(network.next_packet().await, Some(Packet { address: 255, x: 1, y: 2 }));
+    network.shutdown();
+}
@@ -1,14 +1,10 @@
 use num_derive::FromPrimitive;    
 
-#[derive(FromPrimitive, Debug, PartialEq)]
+#[derive(FromPrimitive, Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub enum ParameterMode {
+    #[default]
     Position = 0,
     Immediate = 1,
-}
-
-impl Default for ParameterMode {
-    fn default() -> Self {
-        Self::Position
-    }
+    Relative = 2,
 }
 
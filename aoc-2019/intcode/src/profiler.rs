@@ -0,0 +1,105 @@
+use crate::trace::{TraceEvent, Tracer};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Tracer that counts executed instructions per op code and per address
+///
+/// # Examples
+/// ```
+/// use intcode::{io::QueueIo, machine::Intcode, profiler::Profiler};
+/// let mut profiler = Profiler::default();
+/// Intcode::new(vec![1101, 2, 3, 0, 99]).run_traced(&mut QueueIo::default(), &mut profiler);
+/// assert_eq!(profiler.total(), 2);
+/// println!("{}", profiler.report(10));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    total: u64,
+    by_op_code: HashMap<Cow<'static, str>, u64>,
+    by_address: HashMap<usize, (Cow<'static, str>, u64)>,
+}
+
+impl Profiler {
+    /// Gets the number of instructions executed
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Gets the instruction counts per mnemonic, most executed first
+    pub fn op_codes(&self) -> Vec<(&str, u64)> {
+        let mut counts = self.by_op_code.iter().map(|(op, count)| (op.as_ref(), *count)).collect::<Vec<(&str, u64)>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// Gets the addresses executed most often together with their mnemonic and count
+    ///
+    /// # Arguments
+    /// * `limit` - The maximum number of addresses to return
+    pub fn hot_spots(&self, limit: usize) -> Vec<(usize, &str, u64)> {
+        let mut counts = self.by_address.iter()
+            .map(|(address, (op, count))| (*address, op.as_ref(), *count))
+            .collect::<Vec<(usize, &str, u64)>>();
+        counts.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        counts.truncate(limit);
+        counts
+    }
+
+    /// Renders the op code counts and the hottest addresses as a text report
+    ///
+    /// # Arguments
+    /// * `limit` - The number of addresses to list in the hot spot section
+    pub fn report(&self, limit: usize) -> String {
+        let percent = |count: u64| 100.0 * count as f64 / self.total.max(1) as f64;
+        let mut report = String::new();
+        let _ = writeln!(report, "instructions executed: {}", self.total);
+        let _ = writeln!(report, "by op code:");
+        for (op, count) in self.op_codes() {
+            let _ = writeln!(report, "  {:<4} {:>12} {:>6.2}%", op, count, percent(count));
+        }
+        let _ = writeln!(report, "hot spots:");
+        for (address, op, count) in self.hot_spots(limit) {
+            let _ = writeln!(report, "  {:04} {:<4} {:>12} {:>6.2}%", address, op, count, percent(count));
+        }
+        report
+    }
+}
+
+impl Tracer for Profiler {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.total += 1;
+        match self.by_op_code.get_mut(&event.mnemonic) {
+            Some(count) => *count += 1,
+            None => {
+                self.by_op_code.insert(event.mnemonic.clone(), 1);
+            }
+        }
+        self.by_address.entry(event.ip).or_insert_with(|| (event.mnemonic.clone(), 0)).1 += 1;
+    }
+}
+
+
+#[test]
+fn test_profiler_counts() {
+    use crate::io::QueueIo;
+    use crate::machine::Intcode;
+
+    // Counts down from 3, printing each value
+    let program = vec![4, 10, 1001, 10, -1, 10, 1005, 10, 0, 99, 3];
+    let mut profiler = Profiler::default();
+    Intcode::new(program).run_traced(&mut QueueIo::default(), &mut profiler);
+    assert_eq!(profiler.total(), 10);
+    assert_eq!(profiler.op_codes(), vec![("ADD", 3), ("JT", 3), ("OUT", 3), ("HLT", 1)]);
+    assert_eq!(profiler.hot_spots(2), vec![(0, "OUT", 3), (2, "ADD", 3)]);
+    assert_eq!(profiler.report(1), "\
+instructions executed: 10
+by op code:
+  ADD             3  30.00%
+  JT              3  30.00%
+  OUT             3  30.00%
+  HLT             1  10.00%
+hot spots:
+  0000 OUT             3  30.00%
+");
+}
@@ -0,0 +1,280 @@
+use crate::disassembler::get_mnemonic;
+use crate::instructions::{Instruction, op_code::{OpCode, OpCodeId}};
+use crate::lexer;
+use num_traits::FromPrimitive;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Highest op code that fits in the two digits below the parameter modes
+const MAX_OP_CODE: i64 = 99;
+
+/// Decodes the words of an instruction whose op code matched a registry entry
+type Decoder = Arc<dyn Fn(&[i64]) -> Option<Instruction> + Send + Sync>;
+
+/// An error found while changing an instruction set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// The op code already has an operation, named `existing`
+    Conflict { op_code: i64, existing: String },
+    /// The op code can not be encoded in an instruction
+    InvalidOpCode(i64),
+    /// The op code has no operation to replace
+    NotRegistered(i64),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Conflict { op_code, existing } => write!(f, "op code {} is already registered as {}", op_code, existing),
+            RegistryError::InvalidOpCode(op_code) => write!(f, "op code {} is outside 1 to {}", op_code, MAX_OP_CODE),
+            RegistryError::NotRegistered(op_code) => write!(f, "op code {} is not registered", op_code),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// An operation registered for an op code
+#[derive(Clone)]
+struct Entry {
+    name: Cow<'static, str>,
+    decode: Decoder,
+}
+
+/// The set of operations a machine decodes, keyed by op code
+///
+/// Starts from the standard instructions or from nothing, and can be extended with custom
+/// operations or have built-in operations replaced. A machine only uses an instruction set
+/// when it is given one with `Intcode::with_instruction_set`
+///
+/// # Examples
+/// ```ignore
+/// let mut set = InstructionSet::standard();
+/// set.register::<Negate>(10, "NEG")?;
+/// let machine = Intcode::new(program).with_instruction_set(Arc::new(set));
+/// ```
+#[derive(Clone, Default)]
+pub struct InstructionSet {
+    entries: BTreeMap<i64, Entry>,
+}
+
+impl InstructionSet {
+    /// Creates an instruction set with no operations
+    pub fn empty() -> InstructionSet {
+        InstructionSet::default()
+    }
+
+    /// Creates an instruction set with the standard intcode operations
+    pub fn standard() -> InstructionSet {
+        let mut set = InstructionSet::empty();
+        for op_code in (1..=MAX_OP_CODE).filter(|x| OpCodeId::from_i64(*x).is_some()) {
+            let (name, _) = get_mnemonic(OpCodeId::from_i64(op_code).expect("op code was just checked"));
+            set.insert(op_code, Cow::Borrowed(name), Arc::new(|words: &[i64]| Instruction::parse_from_slice(words)));
+        }
+        set
+    }
+
+    /// Adds an operation for an op code that has none yet
+    ///
+    /// # Arguments
+    /// * `op_code` - The op code in the last two digits of the instruction word, from 1 to 99
+    /// * `name`    - A mnemonic for the operation, used in errors and listings
+    ///
+    /// ## Note
+    /// The operation's `parse_from_slice` receives the whole instruction word including its
+    /// parameter modes. If the operation writes to memory it should return that parameter
+    /// from `get_output`, otherwise the machine assumes it may have written anywhere
+    pub fn register<T: OpCode + Send + Sync + 'static>(&mut self, op_code: i64, name: &str) -> Result<(), RegistryError> {
+        InstructionSet::check(op_code)?;
+        if let Some(existing) = self.entries.get(&op_code) {
+            return Err(RegistryError::Conflict { op_code, existing: existing.name.to_string() });
+        }
+        self.insert(op_code, Cow::Owned(name.to_string()), InstructionSet::decoder::<T>());
+        Ok(())
+    }
+
+    /// Replaces the operation of an op code that is already registered, such as a built-in
+    ///
+    /// # Arguments
+    /// * `op_code` - The op code whose operation is replaced
+    /// * `name`    - A mnemonic for the new operation
+    pub fn replace<T: OpCode + Send + Sync + 'static>(&mut self, op_code: i64, name: &str) -> Result<(), RegistryError> {
+        InstructionSet::check(op_code)?;
+        if !self.entries.contains_key(&op_code) {
+            return Err(RegistryError::NotRegistered(op_code));
+        }
+        self.insert(op_code, Cow::Owned(name.to_string()), InstructionSet::decoder::<T>());
+        Ok(())
+    }
+
+    /// Removes the operation of an op code, so that the machine faults on it
+    pub fn remove(&mut self, op_code: i64) -> Result<(), RegistryError> {
+        match self.entries.remove(&op_code) {
+            Some(_) => Ok(()),
+            None => Err(RegistryError::NotRegistered(op_code))
+        }
+    }
+
+    /// Gets the name an op code was registered with
+    pub fn name(&self, op_code: i64) -> Option<&str> {
+        self.entries.get(&op_code).map(|entry| entry.name.as_ref())
+    }
+
+    /// Gets the name of an op code for trace events, without copying the names of built-in operations
+    pub(crate) fn mnemonic(&self, op_code: i64) -> Option<Cow<'static, str>> {
+        self.entries.get(&op_code).map(|entry| entry.name.clone())
+    }
+
+    /// Gets every registered op code in ascending order
+    pub fn op_codes(&self) -> Vec<i64> {
+        self.entries.keys().copied().collect()
+    }
+
+    /// Decodes the instruction at the start of a slice of a program
+    ///
+    /// Returns `None` if the op code is not registered or the words are not a valid instruction
+    pub fn decode(&self, words: &[i64]) -> Option<Instruction> {
+        let op_code = lexer::parse_instruction_type(*words.first()?)?.op_code;
+        (self.entries.get(&op_code)?.decode)(words)
+    }
+
+    /// Helper function to add or overwrite an entry
+    fn insert(&mut self, op_code: i64, name: Cow<'static, str>, decode: Decoder) {
+        self.entries.insert(op_code, Entry { name, decode });
+    }
+
+    /// Helper function to check that an op code can be encoded
+    fn check(op_code: i64) -> Result<(), RegistryError> {
+        match (1..=MAX_OP_CODE).contains(&op_code) {
+            true => Ok(()),
+            false => Err(RegistryError::InvalidOpCode(op_code))
+        }
+    }
+
+    /// Helper function to create a decoder for a custom operation
+    fn decoder<T: OpCode + Send + Sync + 'static>() -> Decoder {
+        Arc::new(|words: &[i64]| {
+            let op: Arc<dyn OpCode + Send + Sync> = Arc::new(T::parse_from_slice(words)?);
+            Some(Instruction::Custom(op))
+        })
+    }
+}
+
+impl fmt::Debug for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.entries.iter().map(|(op_code, entry)| (op_code, &entry.name))).finish()
+    }
+}
+
+
+#[cfg(test)]
+use crate::{error::IntcodeError, io::IntcodeIo, memory::Memory};
+
+/// Negates its input, an example of an operation outside the standard set
+#[cfg(test)]
+#[derive(Debug)]
+struct Negate {
+    input: lexer::Parameter,
+    output: lexer::Parameter,
+}
+
+#[cfg(test)]
+impl OpCode for Negate {
+    fn parse_from_slice(program: &[i64]) -> Option<Negate> {
+        let instruction = lexer::parse_instruction_type(*program.first()?)?;
+        Some(Negate {
+            input: lexer::Parameter { mode: instruction.c.mode, value: *program.get(1)? },
+            output: lexer::Parameter { mode: instruction.b.mode, value: *program.get(2)? },
+        })
+    }
+
+    fn apply(&self, program: &mut Memory, instruction_pointer: i64, relative_base: &mut i64, _io: &mut dyn IntcodeIo) -> Result<i64, IntcodeError> {
+        let value = lexer::get_parameter_value(&self.input, program, *relative_base)?;
+        program.write(lexer::get_parameter_address(&self.output, *relative_base)?, -value)?;
+        Ok(instruction_pointer + 3)
+    }
+
+    fn get_instruction_pointer_offset(&self) -> i64 {
+        3
+    }
+
+    fn get_op_code(&self) -> i64 {
+        10
+    }
+
+    fn get_output(&self) -> Option<&lexer::Parameter> {
+        Some(&self.output)
+    }
+}
+
+#[test]
+fn test_register_conflicts() {
+    let mut set = InstructionSet::standard();
+    assert_eq!(set.op_codes(), vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 99]);
+    assert_eq!(set.register::<Negate>(10, "NEG"), Ok(()));
+    assert_eq!(set.name(10), Some("NEG"));
+    assert_eq!(set.register::<Negate>(1, "NEG"), Err(RegistryError::Conflict { op_code: 1, existing: String::from("ADD") }));
+    assert_eq!(set.register::<Negate>(10, "NEG"), Err(RegistryError::Conflict { op_code: 10, existing: String::from("NEG") }));
+    assert_eq!(set.register::<Negate>(100, "NEG"), Err(RegistryError::InvalidOpCode(100)));
+    assert_eq!(set.replace::<Negate>(11, "NEG"), Err(RegistryError::NotRegistered(11)));
+
+    assert_eq!(set.decode(&[10, 5, 6]).map(|op| op.get_op_code()), Some(10));
+    assert_eq!(set.decode(&[10, 5]).map(|op| op.get_op_code()), None);
+    assert_eq!(set.decode(&[1002, 4, 3, 4]), Instruction::parse_from_slice(&[1002, 4, 3, 4]));
+    assert!(InstructionSet::empty().decode(&[99]).is_none());
+}
+
+#[test]
+fn test_machine_with_instruction_set() {
+    use crate::machine::{Intcode, RunState};
+
+    let mut set = InstructionSet::standard();
+    set.register::<Negate>(10, "NEG").unwrap();
+    let extended = Arc::new(set);
+    let mut machine = Intcode::new(vec![10, 5, 6, 99, 0, 7, 0]).with_instruction_set(extended.clone());
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.memory().read(6), Ok(-7));
+
+    let mut machine = Intcode::new(vec![10, 5, 6, 99, 0, 7, 0]);
+    assert_eq!(machine.run_until_blocked(), RunState::Faulted);
+    assert_eq!(machine.error(), Some(&IntcodeError::UnknownOpcode { ip: 0, word: 10 }));
+
+    // Replaces addition with negation, which reads one operand fewer
+    let mut set = InstructionSet::standard();
+    set.replace::<Negate>(1, "NEG").unwrap();
+    let mut machine = Intcode::new(vec![101, 5, 0, 99]).with_instruction_set(Arc::new(set));
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+    assert_eq!(machine.memory().read(0), Ok(-5));
+
+    // The set is kept when a machine is restored from a snapshot
+    let mut machine = Intcode::new(vec![10, 5, 6, 99, 0, 7, 0]).with_instruction_set(extended);
+    let checkpoint = machine.snapshot();
+    machine.restore(&checkpoint);
+    assert_eq!(machine.run_until_blocked(), RunState::Halted);
+}
+
+#[test]
+fn test_trace_with_instruction_set() {
+    use crate::io::QueueIo;
+    use crate::machine::{Intcode, RunState};
+    use crate::profiler::Profiler;
+    use crate::trace::{MemoryWrite, TraceEvent};
+
+    // Addition is replaced with negation, which reads one operand fewer
+    let mut set = InstructionSet::standard();
+    set.replace::<Negate>(1, "NEG").unwrap();
+    let set = Arc::new(set);
+    let mut events: Vec<TraceEvent> = Vec::new();
+    let mut machine = Intcode::new(vec![101, 5, 0, 1106, 0, 7, 0, 99]).with_instruction_set(set.clone());
+    machine.run_traced(&mut QueueIo::default(), &mut events);
+    assert_eq!(events[0], TraceEvent { ip: 0, word: 101, mnemonic: "NEG".into(), operands: vec![5], writes: vec![MemoryWrite { address: 0, old: 101, new: -5 }], relative_base: 0 });
+    assert_eq!((events[1].mnemonic.as_ref(), events[2].mnemonic.as_ref()), ("JF", "HLT"));
+
+    // The operands of the negation run past the end of the memory, which reads them as zero
+    let mut machine = Intcode::new(vec![1, 3]).with_instruction_set(set);
+    let mut profiler = Profiler::default();
+    assert_eq!(machine.run_traced(&mut QueueIo::default(), &mut profiler), RunState::Faulted);
+    assert_eq!(profiler.op_codes(), vec![("NEG", 1)]);
+}
@@ -0,0 +1,209 @@
+use crate::error::IntcodeError;
+use crate::io::QueueIo;
+use crate::machine::{ExitReason, Intcode};
+use crate::memory::Memory;
+use rayon::prelude::*;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Number of instructions a candidate may execute before it is given up on
+pub const DEFAULT_STEP_LIMIT: u64 = 1_000_000;
+
+/// An error raised while evaluating a candidate of a search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchError {
+    /// The program faulted, waited on input or reached the step limit
+    Intcode(IntcodeError),
+    /// The candidate does not have one value for each patched address
+    ValueCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::Intcode(e) => write!(f, "{}", e),
+            SearchError::ValueCount { expected, found } => write!(f, "expected {} values, one for each patched address, but found {}", expected, found),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<IntcodeError> for SearchError {
+    fn from(error: IntcodeError) -> Self {
+        SearchError::Intcode(error)
+    }
+}
+
+/// A search for the values that, patched into a program before it runs, leave a target value
+/// at an address once the program halts
+///
+/// Candidates are tried in parallel, ordered so that the first patched address varies slowest,
+/// and the first matching candidate in that order is returned. Each candidate runs for at most
+/// `DEFAULT_STEP_LIMIT` instructions unless another limit is set with `step_limit`
+///
+/// # Examples
+/// ```
+/// use intcode::search::InputSearch;
+/// // Adds the values at the addresses stored in cells 1 and 2 and writes the sum to cell 0
+/// let search = InputSearch::new(vec![1, 0, 0, 0, 99], 0).patch(1, 0..=4).patch(2, 0..=4);
+/// assert_eq!(search.evaluate(&[4, 4]), Ok(198));
+/// assert_eq!(search.find(198), Some(vec![4, 4]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputSearch {
+    memory: Memory,
+    patches: Vec<(i64, RangeInclusive<i64>)>,
+    target_address: i64,
+    step_limit: u64,
+}
+
+impl InputSearch {
+    /// Creates a new search with no patched addresses
+    ///
+    /// # Arguments
+    /// * `program`        - The program to run for every candidate
+    /// * `target_address` - The address that is read once the program halts
+    pub fn new(program: Vec<i64>, target_address: i64) -> InputSearch {
+        InputSearch { memory: Memory::new(program), patches: Vec::new(), target_address, step_limit: DEFAULT_STEP_LIMIT }
+    }
+
+    /// Sets the number of instructions a candidate may execute before it is given up on
+    ///
+    /// # Arguments
+    /// * `limit` - The maximum number of instructions to execute for each candidate
+    pub fn step_limit(mut self, limit: u64) -> InputSearch {
+        self.step_limit = limit;
+        self
+    }
+
+    /// Adds an address that is patched with every value of a range
+    ///
+    /// # Arguments
+    /// * `address` - The address to overwrite before the program runs
+    /// * `values`  - The values to try at that address
+    pub fn patch(mut self, address: i64, values: RangeInclusive<i64>) -> InputSearch {
+        self.patches.push((address, values));
+        self
+    }
+
+    /// Gets the number of candidates the search covers
+    ///
+    /// Returns `None` if there are more candidates than fit in a `usize`
+    pub fn len(&self) -> Option<usize> {
+        if self.patches.iter().any(|(_, values)| values.is_empty()) {
+            return Some(0);
+        }
+        self.patches.iter().try_fold(1_usize, |product, (_, values)| product.checked_mul(range_len(values)?))
+    }
+
+    /// Checks if the search covers no candidates because a range is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Runs the program with one value for each patched address, in the order they were added
+    ///
+    /// Returns the value at the target address once the program halts, or the error that stopped it.
+    /// Returns `SearchError::ValueCount` if there is not exactly one value for each patched address
+    pub fn evaluate(&self, values: &[i64]) -> Result<i64, SearchError> {
+        if values.len() != self.patches.len() {
+            return Err(SearchError::ValueCount { expected: self.patches.len(), found: values.len() });
+        }
+        let mut machine = Intcode::with_memory(self.memory.clone());
+        for ((address, _), value) in self.patches.iter().zip(values) {
+            machine.memory_mut().write(*address, *value)?;
+        }
+        let outcome = machine.run(&mut QueueIo::default(), Some(self.step_limit));
+        let ip = outcome.instruction_pointer;
+        let result = match outcome.reason {
+            ExitReason::Faulted(e) => Err(e),
+            ExitReason::AwaitingInput => Err(IntcodeError::InputExhausted{ ip, word: machine.memory().get(ip) }),
+            ExitReason::StepLimit => Err(IntcodeError::StepLimit { ip, word: machine.memory().get(ip), steps: outcome.steps }),
+            ExitReason::Halted => machine.memory().read(self.target_address)
+        };
+        Ok(result?)
+    }
+
+    /// Finds the first candidate that leaves `target` at the target address
+    ///
+    /// Candidates that fault, wait on input or reach the step limit are skipped. Returns `None`
+    /// without trying anything if the candidates can not be counted, see `len`
+    pub fn find(&self, target: i64) -> Option<Vec<i64>> {
+        (0..self.len()?).into_par_iter()
+                       .map(|index| self.candidate(index))
+                       .find_first(|values| self.evaluate(values) == Ok(target))
+    }
+
+    /// Helper function to get the patched values of a candidate from its position in the search order
+    fn candidate(&self, mut index: usize) -> Vec<i64> {
+        let mut values = vec![0; self.patches.len()];
+        for (i, (_, range)) in self.patches.iter().enumerate().rev() {
+            let count = range_len(range).expect("search has been counted");
+            values[i] = range.start() + (index % count) as i64;
+            index /= count;
+        }
+        values
+    }
+}
+
+/// Helper function to count the values of a range without iterating over it
+fn range_len(range: &RangeInclusive<i64>) -> Option<usize> {
+    match range.is_empty() {
+        true => Some(0),
+        false => usize::try_from(*range.end() as i128 - *range.start() as i128 + 1).ok()
+    }
+}
+
+
+#[test]
+fn test_find_inputs() {
+    let search = InputSearch::new(vec![1, 0, 0, 0, 99], 0).patch(1, 0..=4).patch(2, 0..=4);
+    assert_eq!(search.len(), Some(25));
+    assert_eq!(search.candidate(0), vec![0, 0]);
+    assert_eq!(search.candidate(7), vec![1, 2]);
+    assert_eq!(search.find(100), Some(vec![0, 4]));
+    assert_eq!(search.find(1000), None);
+
+    // Example from day 2, the program is evaluated without patching anything
+    let search = InputSearch::new(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50], 0);
+    assert_eq!(search.evaluate(&[]), Ok(3500));
+    assert_eq!(search.find(3500), Some(vec![]));
+}
+
+#[test]
+fn test_evaluate_faults() {
+    let search = InputSearch::new(vec![1, 0, 0, 0, 99], 0).patch(0, 42..=42);
+    assert_eq!(search.evaluate(&[42]), Err(SearchError::Intcode(IntcodeError::UnknownOpcode { ip: 0, word: 42 })));
+    assert_eq!(search.find(0), None);
+
+    let search = InputSearch::new(vec![3, 0, 99], 0);
+    assert_eq!(search.evaluate(&[]), Err(SearchError::Intcode(IntcodeError::InputExhausted { ip: 0, word: 3 })));
+
+    // Loops forever unless the patched value is zero
+    let search = InputSearch::new(vec![1105, 1, 0, 99], 3).patch(1, 0..=1).step_limit(50);
+    assert_eq!(search.evaluate(&[1]), Err(SearchError::Intcode(IntcodeError::StepLimit { ip: 0, word: 1105, steps: 50 })));
+    assert_eq!(search.find(99), Some(vec![0]));
+    let search = InputSearch::new(vec![1105, 1, 0, 99], 3).patch(1, 1..=2).step_limit(50);
+    assert_eq!(search.find(99), None);
+}
+
+#[test]
+fn test_evaluate_value_count() {
+    let search = InputSearch::new(vec![1, 0, 0, 0, 99], 0).patch(1, 0..=4).patch(2, 0..=4);
+    assert_eq!(search.evaluate(&[4]), Err(SearchError::ValueCount { expected: 2, found: 1 }));
+    assert_eq!(search.evaluate(&[4, 4, 4]), Err(SearchError::ValueCount { expected: 2, found: 3 }));
+}
+
+#[test]
+fn test_len_of_large_searches() {
+    let search = InputSearch::new(vec![99], 0).patch(0, i64::MIN..=i64::MAX).patch(1, 0..=1);
+    assert_eq!(search.len(), None);
+    assert_eq!(search.find(0), None);
+    let empty = RangeInclusive::new(1, 0);
+    assert_eq!(search.clone().patch(2, empty.clone()).len(), Some(0));
+    assert!(search.patch(2, empty).is_empty());
+
+    let search = InputSearch::new(vec![99], 0).patch(0, 0..=u32::MAX as i64).patch(1, 0..=u32::MAX as i64);
+    assert_eq!(search.len(), (u32::MAX as usize + 1).checked_mul(u32::MAX as usize + 1));
+}
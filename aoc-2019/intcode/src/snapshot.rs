@@ -0,0 +1,135 @@
+use crate::error::IntcodeError;
+use crate::io::QueueIo;
+use crate::machine::RunState;
+use crate::memory::Memory;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// An error raised while saving or loading a snapshot file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The file could not be read or written
+    Io(String),
+    /// The file is not a valid snapshot
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(message) => write!(f, "{}", message),
+            SnapshotError::Invalid(message) => write!(f, "invalid snapshot: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> Self {
+        SnapshotError::Io(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(error: serde_json::Error) -> Self {
+        SnapshotError::Invalid(error.to_string())
+    }
+}
+
+/// The complete state of an intcode machine at a point in time
+///
+/// Created with `Intcode::snapshot` and turned back into a machine with `Intcode::restore`
+/// or `Intcode::from_snapshot`. Snapshots can be saved to a file so that a long running
+/// program can be resumed later
+///
+/// # Examples
+/// ```ignore
+/// machine.snapshot().save("checkpoint.json")?;
+/// let resumed = Intcode::from_snapshot(Snapshot::load("checkpoint.json")?);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) memory: Memory,
+    pub(crate) instruction_pointer: usize,
+    pub(crate) relative_base: i64,
+    pub(crate) state: RunState,
+    pub(crate) error: Option<IntcodeError>,
+    pub(crate) io: QueueIo,
+}
+
+impl Snapshot {
+    /// Gets the memory at the time of the snapshot
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Gets the instruction pointer at the time of the snapshot
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    /// Gets the run state at the time of the snapshot
+    pub fn state(&self) -> RunState {
+        self.state
+    }
+
+    /// Writes the snapshot to a file as JSON
+    ///
+    /// # Arguments
+    /// * `filename` - Path of the file to create or overwrite
+    pub fn save(&self, filename: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a snapshot that was written with `save`
+    ///
+    /// # Arguments
+    /// * `filename` - Path of the snapshot file
+    pub fn load(filename: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+        let reader = BufReader::new(File::open(filename)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+
+#[test]
+fn test_save_and_load() {
+    use crate::machine::Intcode;
+
+    let path = std::env::temp_dir().join(format!("intcode-snapshot-{}.json", std::process::id()));
+    let mut machine = Intcode::with_memory(Memory::paged(&[109, 3, 3, 1 << 20, 4, 1 << 20, 99]));
+    machine.push_input(5);
+    machine.push_input(6);
+    machine.step();
+    machine.step();
+    machine.snapshot().save(&path).unwrap();
+
+    let snapshot = Snapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(snapshot, machine.snapshot());
+    assert_eq!(snapshot.instruction_pointer(), 4);
+
+    let mut resumed = Intcode::from_snapshot(snapshot);
+    assert_eq!(resumed.run_until_blocked(), RunState::Halted);
+    assert_eq!(resumed.relative_base(), 3);
+    assert_eq!(resumed.drain_outputs(), vec![5]);
+}
+
+#[test]
+fn test_load_invalid_snapshot() {
+    let path = std::env::temp_dir().join(format!("intcode-invalid-snapshot-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"memory":{"Paged":{"len":3,"pages":[[0,[1,2,3]]]}}}"#).unwrap();
+    let result = Snapshot::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(SnapshotError::Invalid(_))));
+    assert!(matches!(Snapshot::load("does-not-exist.json"), Err(SnapshotError::Io(_))));
+}
@@ -0,0 +1,254 @@
+use crate::cache::MAX_CACHED_ADDRESS;
+use crate::error::IntcodeError;
+use crate::instructions::{Instruction, MAX_INSTRUCTION_LENGTH, op_code::{OpCode, OpCodeId}};
+use crate::io::IntcodeIo;
+use crate::lexer;
+use crate::memory::Memory;
+use crate::parameters::ParameterMode;
+use num_traits::FromPrimitive;
+use std::fmt;
+use std::sync::Arc;
+
+/// What a translated instruction did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Execution continues at `ip`, `written` is the address the instruction wrote to if any
+    Next { ip: i64, written: Option<i64> },
+    /// The program halted
+    Halt,
+}
+
+/// An instruction translated into a closure with its operands and next address resolved
+///
+/// Called with the memory, the relative base and the I/O of the machine
+pub type Operation = Arc<dyn Fn(&mut Memory, &mut i64, &mut dyn IntcodeIo) -> Result<Flow, IntcodeError> + Send + Sync>;
+
+/// State of the translation of the instruction starting at an address
+#[derive(Clone)]
+enum Slot {
+    /// Not executed yet
+    Empty,
+    /// Translated into an operation covering `length` words
+    Translated { operation: Operation, length: usize },
+    /// Written to after it was translated, or a custom operation, and left to the interpreter
+    Interpreted,
+}
+
+/// An operand whose addressing mode was resolved when the instruction was translated
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    Immediate(i64),
+    Position(i64),
+    Relative(i64),
+}
+
+impl Source {
+    /// Creates an operand from its mode and the word following the instruction word
+    fn new(mode: ParameterMode, value: i64) -> Source {
+        match mode {
+            ParameterMode::Immediate => Source::Immediate(value),
+            ParameterMode::Position => Source::Position(value),
+            ParameterMode::Relative => Source::Relative(value),
+        }
+    }
+
+    /// Reads the value of the operand
+    #[inline]
+    fn read(self, memory: &Memory, relative_base: i64) -> Result<i64, IntcodeError> {
+        match self {
+            Source::Immediate(value) => Ok(value),
+            Source::Position(address) => memory.read(address),
+            Source::Relative(offset) => memory.read(relative(relative_base, offset)?),
+        }
+    }
+
+    /// Gets the address that an output operand writes to
+    #[inline]
+    fn address(self, relative_base: i64) -> Result<i64, IntcodeError> {
+        match self {
+            Source::Immediate(_) => Err(IntcodeError::WriteToImmediate { ip: 0, word: 0 }),
+            Source::Position(address) => Ok(address),
+            Source::Relative(offset) => relative(relative_base, offset),
+        }
+    }
+}
+
+/// Error raised by arithmetic that overflows, the machine fills in the location
+const OVERFLOW: IntcodeError = IntcodeError::Overflow { ip: 0, word: 0 };
+
+/// Helper function to add a relative base and an offset
+#[inline]
+fn relative(relative_base: i64, offset: i64) -> Result<i64, IntcodeError> {
+    relative_base.checked_add(offset).ok_or(OVERFLOW)
+}
+
+/// Instructions translated into closures, keyed by the address they start at
+///
+/// Each instruction is translated the first time it is executed. Once a write lands on a
+/// translated instruction that address falls back to the interpreter for good, so that self
+/// modifying code neither runs stale translations nor keeps translating the same address
+///
+/// ## Note
+/// A write to memory must be reported with `invalidate`, like for the `DecodeCache`
+#[derive(Clone, Default)]
+pub struct ThreadedCode {
+    slots: Vec<Slot>,
+}
+
+impl ThreadedCode {
+    /// Gets the operation translated for an address
+    pub fn get(&self, address: usize) -> Option<&Operation> {
+        match self.slots.get(address) {
+            Some(Slot::Translated { operation, .. }) => Some(operation),
+            _ => None
+        }
+    }
+
+    /// Checks if an address has been handed back to the interpreter
+    pub fn is_interpreted(&self, address: usize) -> bool {
+        matches!(self.slots.get(address), Some(Slot::Interpreted))
+    }
+
+    /// Translates the instruction starting at an address
+    ///
+    /// # Arguments
+    /// * `address`     - Address of the instruction word
+    /// * `instruction` - The decoded instruction, custom operations are left to the interpreter
+    /// * `words`       - The instruction word followed by its operands
+    ///
+    /// Returns the translated operation, or `None` if the instruction is left to the interpreter
+    pub fn translate(&mut self, address: usize, instruction: &Instruction, words: &[i64]) -> Option<Operation> {
+        if address > MAX_CACHED_ADDRESS {
+            return None;
+        }
+        if address >= self.slots.len() {
+            self.slots.resize(address + 1, Slot::Empty);
+        }
+        let length = instruction.get_instruction_pointer_offset() as usize;
+        self.slots[address] = match translate(address as i64, instruction, words) {
+            Some(operation) => Slot::Translated { operation: operation.clone(), length },
+            None => Slot::Interpreted
+        };
+        self.get(address).cloned()
+    }
+
+    /// Hands every translated instruction that covers a written address back to the interpreter
+    pub fn invalidate(&mut self, address: i64) {
+        let address = match usize::try_from(address) {
+            Ok(a) => a,
+            Err(_) => return
+        };
+        let first = address.saturating_sub(MAX_INSTRUCTION_LENGTH - 1);
+        for start in first..(address + 1).min(self.slots.len()) {
+            if let Slot::Translated { length, .. } = self.slots[start] {
+                if start + length > address {
+                    self.slots[start] = Slot::Interpreted;
+                }
+            }
+        }
+    }
+
+    /// Drops every translation, including the addresses handed back to the interpreter
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+}
+
+impl fmt::Debug for ThreadedCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let translated = self.slots.iter().filter(|s| matches!(s, Slot::Translated { .. })).count();
+        let interpreted = self.slots.iter().filter(|s| matches!(s, Slot::Interpreted)).count();
+        f.debug_struct("ThreadedCode").field("translated", &translated).field("interpreted", &interpreted).finish()
+    }
+}
+
+/// Helper function to translate a decoded instruction into a closure
+fn translate(address: i64, instruction: &Instruction, words: &[i64]) -> Option<Operation> {
+    if let Instruction::Custom(_) = instruction {
+        return None;
+    }
+    let modes = lexer::parse_instruction_type(*words.first()?)?;
+    let operand = |i: usize, mode: ParameterMode| Source::new(mode, words.get(i).copied().unwrap_or(0));
+    let (a, b, c) = (operand(1, modes.c.mode), operand(2, modes.b.mode), operand(3, modes.a.mode));
+    let next = address + instruction.get_instruction_pointer_offset();
+
+    let operation: Operation = match OpCodeId::from_i64(instruction.get_op_code())? {
+        OpCodeId::Add => Arc::new(move |memory, rb, _| {
+            let value = a.read(memory, *rb)?.checked_add(b.read(memory, *rb)?).ok_or(OVERFLOW)?;
+            let written = c.address(*rb)?;
+            memory.write(written, value)?;
+            Ok(Flow::Next { ip: next, written: Some(written) })
+        }),
+        OpCodeId::Multiply => Arc::new(move |memory, rb, _| {
+            let value = a.read(memory, *rb)?.checked_mul(b.read(memory, *rb)?).ok_or(OVERFLOW)?;
+            let written = c.address(*rb)?;
+            memory.write(written, value)?;
+            Ok(Flow::Next { ip: next, written: Some(written) })
+        }),
+        OpCodeId::Input => Arc::new(move |memory, rb, io| {
+            let written = a.address(*rb)?;
+            match io.read() {
+                Some(value) => memory.write(written, value)?,
+                None => return Err(IntcodeError::InputExhausted { ip: 0, word: 0 })
+            }
+            Ok(Flow::Next { ip: next, written: Some(written) })
+        }),
+        OpCodeId::Output => Arc::new(move |memory, rb, io| {
+            io.write(a.read(memory, *rb)?);
+            Ok(Flow::Next { ip: next, written: None })
+        }),
+        OpCodeId::JumpIfTrue => Arc::new(move |memory, rb, _| {
+            let (value, target) = (a.read(memory, *rb)?, b.read(memory, *rb)?);
+            Ok(Flow::Next { ip: if value != 0 { target } else { next }, written: None })
+        }),
+        OpCodeId::JumpIfFalse => Arc::new(move |memory, rb, _| {
+            let (value, target) = (a.read(memory, *rb)?, b.read(memory, *rb)?);
+            Ok(Flow::Next { ip: if value == 0 { target } else { next }, written: None })
+        }),
+        OpCodeId::LessThan => Arc::new(move |memory, rb, _| {
+            let value = (a.read(memory, *rb)? < b.read(memory, *rb)?) as i64;
+            let written = c.address(*rb)?;
+            memory.write(written, value)?;
+            Ok(Flow::Next { ip: next, written: Some(written) })
+        }),
+        OpCodeId::Equals => Arc::new(move |memory, rb, _| {
+            let value = (a.read(memory, *rb)? == b.read(memory, *rb)?) as i64;
+            let written = c.address(*rb)?;
+            memory.write(written, value)?;
+            Ok(Flow::Next { ip: next, written: Some(written) })
+        }),
+        OpCodeId::AdjustRelativeBase => Arc::new(move |memory, rb, _| {
+            *rb = rb.checked_add(a.read(memory, *rb)?).ok_or(OVERFLOW)?;
+            Ok(Flow::Next { ip: next, written: None })
+        }),
+        OpCodeId::Complete => Arc::new(|_, _, _| Ok(Flow::Halt)),
+    };
+    Some(operation)
+}
+
+
+#[test]
+fn test_translate_and_invalidate() {
+    use crate::io::QueueIo;
+
+    let mut code = ThreadedCode::default();
+    let words = [1101, 2, 3, 5];
+    let operation = code.translate(0, &Instruction::parse_from_slice(&words).unwrap(), &words).unwrap();
+    let mut memory = Memory::new(vec![1101, 2, 3, 5, 99]);
+    assert_eq!(operation(&mut memory, &mut 0, &mut QueueIo::default()), Ok(Flow::Next { ip: 4, written: Some(5) }));
+    assert_eq!(memory.read(5), Ok(5));
+    assert!(code.get(0).is_some());
+
+    code.invalidate(4);
+    assert!(code.get(0).is_some());
+    code.invalidate(3);
+    assert!(code.get(0).is_none());
+    assert!(code.is_interpreted(0));
+
+    let words = [21101, 2, 3, 5];
+    let operation = code.translate(4, &Instruction::parse_from_slice(&words).unwrap(), &words).unwrap();
+    assert_eq!(operation(&mut memory, &mut -7, &mut QueueIo::default()), Err(IntcodeError::NegativeAddress { ip: 0, word: 0, address: -2 }));
+
+    code.clear();
+    assert!(!code.is_interpreted(0));
+}
@@ -0,0 +1,250 @@
+use crate::instructions::{Instruction, MAX_INSTRUCTION_LENGTH, op_code::OpCode};
+use crate::lexer::{self, Parameter};
+use crate::memory::Memory;
+use crate::parameters::ParameterMode;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// A memory cell written by an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryWrite {
+    pub address: i64,
+    pub old: i64,
+    pub new: i64,
+}
+
+/// Record of a single executed instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// Address of the instruction
+    pub ip: usize,
+    /// Raw instruction word
+    pub word: i64,
+    /// Name of the executed instruction in the machine's instruction set
+    pub mnemonic: Cow<'static, str>,
+    /// Resolved values of the input operands
+    pub operands: Vec<i64>,
+    /// Memory cells written by the instruction
+    pub writes: Vec<MemoryWrite>,
+    /// Relative base before the instruction executed
+    pub relative_base: i64,
+}
+
+/// Hook that receives every instruction executed by `Intcode::step_traced`
+pub trait Tracer {
+    /// Tracers that ignore every event set this to false so that the machine can skip
+    /// decoding operands and building events altogether
+    const ENABLED: bool = true;
+
+    /// Receives an executed instruction
+    fn trace(&mut self, event: &TraceEvent);
+}
+
+/// Tracer that does nothing, used by the untraced stepping functions
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoTrace;
+
+impl Tracer for NoTrace {
+    const ENABLED: bool = false;
+
+    #[inline(always)]
+    fn trace(&mut self, _event: &TraceEvent) {}
+}
+
+/// Collects every event in memory
+impl Tracer for Vec<TraceEvent> {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.push(event.clone());
+    }
+}
+
+/// Sends every event to both tracers
+impl<A: Tracer, B: Tracer> Tracer for (A, B) {
+    const ENABLED: bool = A::ENABLED || B::ENABLED;
+
+    fn trace(&mut self, event: &TraceEvent) {
+        if A::ENABLED {
+            self.0.trace(event);
+        }
+        if B::ENABLED {
+            self.1.trace(event);
+        }
+    }
+}
+
+/// A memory write together with the instruction that made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoggedWrite {
+    /// Number of instructions executed before the one that wrote
+    pub step: u64,
+    /// Address of the instruction that wrote
+    pub ip: usize,
+    /// The written cell
+    pub write: MemoryWrite,
+}
+
+/// Tracer that records every memory write in the order they happened
+///
+/// # Examples
+/// ```
+/// use intcode::{io::QueueIo, machine::Intcode, trace::WriteLog};
+/// let mut log = WriteLog::default();
+/// Intcode::new(vec![1101, 2, 3, 9, 1101, 4, 5, 9, 99]).run_traced(&mut QueueIo::default(), &mut log);
+/// assert_eq!(log.len(), 2);
+/// assert_eq!(log.last_write(9).map(|w| (w.step, w.ip, w.write.new)), Some((1, 4, 9)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteLog {
+    writes: Vec<LoggedWrite>,
+    step: u64,
+}
+
+impl WriteLog {
+    /// Gets every recorded write, oldest first
+    pub fn writes(&self) -> &[LoggedWrite] {
+        &self.writes
+    }
+
+    /// Gets the most recent write to an address
+    pub fn last_write(&self, address: i64) -> Option<&LoggedWrite> {
+        self.writes.iter().rev().find(|w| w.write.address == address)
+    }
+
+    /// Gets the number of recorded writes
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Checks if no write has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+}
+
+impl Tracer for WriteLog {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.writes.extend(event.writes.iter().map(|write| LoggedWrite { step: self.step, ip: event.ip, write: *write }));
+        self.step += 1;
+    }
+}
+
+/// Tracer that writes one JSON object per executed instruction
+///
+/// # Examples
+/// ```
+/// use intcode::{io::QueueIo, machine::Intcode, trace::JsonLinesTracer};
+/// let mut tracer = JsonLinesTracer::new(Vec::new());
+/// Intcode::new(vec![1101, 2, 3, 0, 99]).run_traced(&mut QueueIo::default(), &mut tracer);
+/// let json = String::from_utf8(tracer.into_inner()).unwrap();
+/// assert!(json.starts_with(r#"{"step":0,"ip":0,"word":1101,"op":"ADD""#));
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesTracer<W: Write> {
+    writer: W,
+    step: u64,
+}
+
+impl<W: Write> JsonLinesTracer<W> {
+    /// Creates a new tracer that writes to `writer`
+    pub fn new(writer: W) -> JsonLinesTracer<W> {
+        JsonLinesTracer { writer, step: 0 }
+    }
+
+    /// Consumes the tracer and returns the writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Tracer for JsonLinesTracer<W> {
+    /// Writes the event as a single line, write errors are ignored so tracing never stops a run
+    fn trace(&mut self, event: &TraceEvent) {
+        let operands = event.operands.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",");
+        let writes = event.writes.iter()
+            .map(|w| format!(r#"{{"address":{},"old":{},"new":{}}}"#, w.address, w.old, w.new))
+            .collect::<Vec<String>>()
+            .join(",");
+        let _ = writeln!(self.writer, r#"{{"step":{},"ip":{},"word":{},"op":{},"operands":[{}],"writes":[{}],"relative_base":{}}}"#,
+                         self.step, event.ip, event.word, serde_json::Value::from(event.mnemonic.as_ref()), operands, writes, event.relative_base);
+        self.step += 1;
+    }
+}
+
+/// An event that has been decoded before the instruction executed
+pub(crate) struct PendingEvent {
+    event: TraceEvent,
+    write_address: Option<i64>,
+}
+
+impl PendingEvent {
+    /// Resolves the input operands of the instruction the machine decoded at `ip`
+    ///
+    /// ## Note
+    /// The parameters are read with the modes of the instruction word, and the output
+    /// parameter reported by the instruction is the last one
+    ///
+    /// # Arguments
+    /// * `memory`        - The memory before the instruction executes
+    /// * `ip`            - Address of the instruction
+    /// * `relative_base` - The relative base before the instruction executes
+    /// * `instruction`   - The decoded instruction that is about to execute
+    /// * `mnemonic`      - Name of the instruction in the machine's instruction set
+    pub(crate) fn begin(memory: &Memory, ip: usize, relative_base: i64, instruction: &Instruction, mnemonic: Cow<'static, str>) -> PendingEvent {
+        let word = memory.get(ip);
+        let modes = lexer::parse_instruction_type(word).map_or([ParameterMode::Position; 3], |i| [i.c.mode, i.b.mode, i.a.mode]);
+        let length = usize::try_from(instruction.get_instruction_pointer_offset()).unwrap_or(1).clamp(1, MAX_INSTRUCTION_LENGTH);
+        let mut inputs = (1..length)
+            .map(|i| Parameter { mode: modes[i - 1], value: memory.get(ip + i) })
+            .collect::<Vec<Parameter>>();
+        let write_address = instruction.get_output().and_then(|output| {
+            inputs.pop();
+            lexer::get_parameter_address(output, relative_base).ok()
+        });
+        let operands = inputs.iter()
+            .map(|input| lexer::get_parameter_value(input, memory, relative_base).unwrap_or(0))
+            .collect();
+        let writes = write_address.into_iter()
+            .map(|address| MemoryWrite { address, old: memory.read(address).unwrap_or(0), new: 0 })
+            .collect();
+        let event = TraceEvent { ip, word, mnemonic, operands, writes, relative_base };
+        PendingEvent { event, write_address }
+    }
+
+    /// Completes the event with the values written by the instruction
+    pub(crate) fn finish(mut self, memory: &Memory) -> TraceEvent {
+        if let (Some(address), Some(write)) = (self.write_address, self.event.writes.first_mut()) {
+            write.new = memory.read(address).unwrap_or(0);
+        }
+        self.event
+    }
+}
+
+
+#[test]
+fn test_trace_events() {
+    use crate::io::QueueIo;
+    use crate::machine::{Intcode, RunState};
+
+    let mut events: Vec<TraceEvent> = Vec::new();
+    let mut machine = Intcode::new(vec![109, 1, 21101, 2, 3, 3, 204, 3, 99]);
+    let mut io = QueueIo::default();
+    assert_eq!(machine.run_traced(&mut io, &mut events), RunState::Halted);
+    assert_eq!(io.drain_outputs(), vec![5]);
+    assert_eq!(events, vec![
+        TraceEvent { ip: 0, word: 109, mnemonic: "ARB".into(), operands: vec![1], writes: vec![], relative_base: 0 },
+        TraceEvent { ip: 2, word: 21101, mnemonic: "ADD".into(), operands: vec![2, 3], writes: vec![MemoryWrite { address: 4, old: 3, new: 5 }], relative_base: 1 },
+        TraceEvent { ip: 6, word: 204, mnemonic: "OUT".into(), operands: vec![5], writes: vec![], relative_base: 1 },
+        TraceEvent { ip: 8, word: 99, mnemonic: "HLT".into(), operands: vec![], writes: vec![], relative_base: 1 },
+    ]);
+}
+
+#[test]
+fn test_json_lines_tracer() {
+    let mut tracer = JsonLinesTracer::new(Vec::new());
+    tracer.trace(&TraceEvent { ip: 4, word: 1002, mnemonic: "MUL".into(), operands: vec![33, 3], writes: vec![MemoryWrite { address: 4, old: 33, new: 99 }], relative_base: 0 });
+    tracer.trace(&TraceEvent { ip: 8, word: 99, mnemonic: "HLT".into(), operands: vec![], writes: vec![], relative_base: 0 });
+    assert_eq!(String::from_utf8(tracer.into_inner()).unwrap(), "\
+{\"step\":0,\"ip\":4,\"word\":1002,\"op\":\"MUL\",\"operands\":[33,3],\"writes\":[{\"address\":4,\"old\":33,\"new\":99}],\"relative_base\":0}
+{\"step\":1,\"ip\":8,\"word\":99,\"op\":\"HLT\",\"operands\":[],\"writes\":[],\"relative_base\":0}
+");
+}
@@ -0,0 +1,75 @@
+//! Differential tests of the threaded backend against the interpreter
+
+use intcode::io::QueueIo;
+use intcode::machine::{Backend, Intcode, RunOutcome};
+use proptest::prelude::*;
+
+/// Op codes of the standard instruction set
+const OP_CODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+/// Number of cells in the memory of generated programs
+const MEMORY_SIZE: usize = 32;
+
+/// Number of instructions after which a generated program is stopped
+const STEP_LIMIT: u64 = 500;
+
+/// Everything observable about a machine after a run
+#[derive(Debug, PartialEq)]
+struct Observed {
+    outcome: RunOutcome,
+    memory: Vec<i64>,
+    relative_base: i64,
+}
+
+/// Helper function to run a program on a backend
+fn observe(program: &[i64], inputs: &[i64], backend: Backend) -> Observed {
+    let mut machine = Intcode::new(program.to_vec()).with_backend(backend);
+    let outcome = machine.run(&mut QueueIo::new(inputs.to_vec()), Some(STEP_LIMIT));
+    Observed { outcome, relative_base: machine.relative_base(), memory: machine.into_memory().into_vec().unwrap() }
+}
+
+/// Helper function to generate a cell that is an instruction word with any modes or a small value
+fn cell() -> impl Strategy<Value = i64> {
+    let instruction = (prop::sample::select(OP_CODES.to_vec()), 0..3i64, 0..3i64, 0..3i64)
+        .prop_map(|(op_code, c, b, a)| op_code + c * 100 + b * 1_000 + a * 10_000);
+    prop_oneof![instruction, -4..MEMORY_SIZE as i64 + 4]
+}
+
+#[test]
+fn self_modifying_code_falls_back_to_the_interpreter() {
+    // Outputs n and adds a step to it until it reaches 20, rewriting the immediate step of
+    // its own add instruction every time, so a stale translation would count in ones
+    let program = vec![4, 20, 1001, 20, 1, 20, 1001, 4, 1, 4, 1007, 20, 20, 21, 1005, 21, 0, 99, 0, 0, 0, 0];
+    for backend in [Backend::Interpreter, Backend::Threaded] {
+        assert_eq!(observe(&program, &[], backend).outcome.outputs, vec![0, 1, 3, 6, 10, 15], "{:?}", backend);
+    }
+}
+
+#[test]
+fn backend_can_change_during_a_run() {
+    let program = vec![3, 13, 1001, 13, -1, 13, 4, 13, 1005, 13, 2, 99, 0, 0];
+    let mut machine = Intcode::new(program.clone());
+    machine.push_input(6);
+    let mut outputs = Vec::new();
+    let mut backend = Backend::Threaded;
+    while let Some(value) = machine.run_until_output() {
+        outputs.push(value);
+        machine.set_backend(backend);
+        backend = match backend {
+            Backend::Threaded => Backend::Interpreter,
+            Backend::Interpreter => Backend::Threaded,
+        };
+    }
+    assert_eq!(outputs, vec![5, 4, 3, 2, 1, 0]);
+}
+
+proptest! {
+    #[test]
+    fn threaded_matches_interpreter(
+        program in prop::collection::vec(cell(), MEMORY_SIZE),
+        inputs in prop::collection::vec(-4..MEMORY_SIZE as i64, 0..4),
+    ) {
+        let interpreted = observe(&program, &inputs, Backend::Interpreter);
+        prop_assert_eq!(observe(&program, &inputs, Backend::Threaded), interpreted);
+    }
+}
@@ -0,0 +1,102 @@
+//! Conformance suite built from the example programs given in the day 2, 5 and 9 puzzles
+
+use intcode::io::QueueIo;
+use intcode::machine::{Backend, ExitReason, Intcode};
+
+/// Number of instructions after which an example is assumed to never halt
+const STEP_LIMIT: u64 = 100_000;
+
+/// Helper function to run a program to completion with a list of inputs on every backend
+///
+/// Returns the final memory and every output
+fn run(program: &[i64], inputs: &[i64]) -> (Vec<i64>, Vec<i64>) {
+    let results = [Backend::Interpreter, Backend::Threaded].map(|backend| {
+        let mut machine = Intcode::new(program.to_vec()).with_backend(backend);
+        let outcome = machine.run(&mut QueueIo::new(inputs.to_vec()), Some(STEP_LIMIT));
+        assert_eq!(outcome.reason, ExitReason::Halted, "program {:?} did not halt on {:?}", program, backend);
+        (machine.into_memory().into_vec().unwrap(), outcome.outputs)
+    });
+    let [interpreted, threaded] = results;
+    assert_eq!(interpreted, threaded, "backends disagree on program {:?}", program);
+    interpreted
+}
+
+/// Helper function to check the outputs of a program for each input
+fn assert_outputs(program: &[i64], cases: &[(i64, i64)]) {
+    for (input, expected) in cases {
+        assert_eq!(run(program, &[*input]).1, vec![*expected], "program {:?} with input {}", program, input);
+    }
+}
+
+#[test]
+fn day_2_examples() {
+    let cases: [(&[i64], &[i64]); 5] = [
+        (&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50], &[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]),
+        (&[1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]),
+        (&[2, 3, 0, 3, 99], &[2, 3, 0, 6, 99]),
+        (&[2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801]),
+        (&[1, 1, 1, 4, 99, 5, 6, 0, 99], &[30, 1, 1, 4, 2, 5, 6, 0, 99]),
+    ];
+    for (program, expected) in cases {
+        assert_eq!(run(program, &[]).0, expected);
+    }
+}
+
+#[test]
+fn day_5_io_and_modes() {
+    assert_eq!(run(&[3, 0, 4, 0, 99], &[-17]).1, vec![-17]);
+    assert_eq!(run(&[1002, 4, 3, 4, 33], &[]).0, vec![1002, 4, 3, 4, 99]);
+    assert_eq!(run(&[1101, 100, -1, 4, 0], &[]).0, vec![1101, 100, -1, 4, 99]);
+}
+
+#[test]
+fn day_5_comparisons() {
+    // Equal to 8 and less than 8, in position mode and then in immediate mode
+    assert_outputs(&[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], &[(8, 1), (7, 0), (-8, 0)]);
+    assert_outputs(&[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8], &[(7, 1), (8, 0), (-9, 1)]);
+    assert_outputs(&[3, 3, 1108, -1, 8, 3, 4, 3, 99], &[(8, 1), (9, 0)]);
+    assert_outputs(&[3, 3, 1107, -1, 8, 3, 4, 3, 99], &[(7, 1), (8, 0)]);
+}
+
+#[test]
+fn day_5_jumps() {
+    // Output 0 if the input was zero and 1 otherwise, including negative inputs
+    assert_outputs(&[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9], &[(0, 0), (5, 1), (-5, 1)]);
+    assert_outputs(&[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1], &[(0, 0), (5, 1), (-5, 1)]);
+
+    // Output 999 below 8, 1000 for 8 and 1001 above 8
+    let program = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31,
+        1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104,
+        999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99,
+    ];
+    assert_outputs(&program, &[(-3, 999), (7, 999), (8, 1000), (9, 1001), (1 << 40, 1001)]);
+}
+
+#[test]
+fn day_9_examples() {
+    let quine = [109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+    assert_eq!(run(&quine, &[]).1, quine.to_vec());
+    assert_eq!(run(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[]).1, vec![1219070632396864]);
+    assert_eq!(run(&[104, 1125899906842624, 99], &[]).1, vec![1125899906842624]);
+}
+
+#[test]
+fn day_9_relative_mode() {
+    // Reads and writes through the relative base in both directions
+    assert_eq!(run(&[109, 19, 204, -14, 99], &[]).1, vec![0]);
+    assert_eq!(run(&[109, 10, 203, -2, 204, -2, 99], &[42]).1, vec![42]);
+    assert_eq!(run(&[109, 4, 21101, 2, 3, 3, 99, 0], &[]).0, vec![109, 4, 21101, 2, 3, 3, 99, 5]);
+    assert_eq!(run(&[109, -1, 4, 1, 99], &[]).1, vec![-1]);
+    assert_eq!(run(&[109, -1, 104, 1, 99], &[]).1, vec![1]);
+    assert_eq!(run(&[109, -1, 204, 1, 99], &[]).1, vec![109]);
+    assert_eq!(run(&[109, 1, 9, 2, 204, -6, 99], &[]).1, vec![204]);
+    assert_eq!(run(&[109, 1, 109, 9, 204, -6, 99], &[]).1, vec![204]);
+    assert_eq!(run(&[109, 1, 209, -1, 204, -106, 99], &[]).1, vec![204]);
+}
+
+#[test]
+fn halt_ignores_parameter_modes() {
+    assert_eq!(run(&[11199, 4, 0], &[]).1, Vec::<i64>::new());
+    assert_eq!(run(&[104, 3, 22299], &[]).1, vec![3]);
+}
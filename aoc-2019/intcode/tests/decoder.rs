@@ -0,0 +1,187 @@
+//! Differential tests of the decoder and of single steps against a small reference model
+
+use intcode::instructions::{Instruction, op_code::OpCode};
+use intcode::lexer::Parameter;
+use intcode::machine::{Intcode, RunState};
+use intcode::parameters::ParameterMode;
+use proptest::prelude::*;
+
+/// Op codes of the standard instruction set
+const OP_CODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+/// Number of cells in the memory of generated programs
+const MEMORY_SIZE: usize = 24;
+
+/// An instruction as the reference model understands it
+#[derive(Debug, PartialEq)]
+struct Decoded {
+    op_code: i64,
+    length: i64,
+    modes: [i64; 3],
+    writes: bool,
+}
+
+/// Helper function to decode an instruction word straight from the puzzle description
+fn reference_decode(word: i64) -> Option<Decoded> {
+    if !(0..100_000).contains(&word) {
+        return None;
+    }
+    let modes = [word / 100 % 10, word / 1_000 % 10, word / 10_000 % 10];
+    if modes.iter().any(|m| *m > 2) {
+        return None;
+    }
+    let (length, writes) = match word % 100 {
+        1 | 2 | 7 | 8 => (4, true),
+        3 => (2, true),
+        4 | 9 => (2, false),
+        5 | 6 => (3, false),
+        99 => (1, false),
+        _ => return None
+    };
+    Some(Decoded { op_code: word % 100, length, modes, writes })
+}
+
+/// Helper function to build the parameter mode from a mode digit
+fn mode(digit: i64) -> ParameterMode {
+    match digit {
+        0 => ParameterMode::Position,
+        1 => ParameterMode::Immediate,
+        _ => ParameterMode::Relative,
+    }
+}
+
+/// What a single step did according to the reference model
+#[derive(Debug, PartialEq)]
+enum Step {
+    Next { ip: i64, relative_base: i64, output: Option<i64> },
+    Halted,
+    Faulted,
+}
+
+/// Helper function to execute one instruction straight from the puzzle description
+fn reference_step(memory: &mut Vec<i64>, ip: usize, relative_base: i64, input: i64) -> Step {
+    let decoded = match reference_decode(memory[ip]) {
+        Some(d) => d,
+        None => return Step::Faulted
+    };
+    let address = |i: usize, memory: &Vec<i64>| -> Option<i64> {
+        let value = *memory.get(ip + i + 1).unwrap_or(&0);
+        match decoded.modes[i] {
+            0 => Some(value),
+            2 => Some(relative_base + value),
+            _ => None
+        }
+    };
+    let read = |i: usize, memory: &Vec<i64>| -> Option<i64> {
+        match address(i, memory) {
+            Some(a) if a < 0 => None,
+            Some(a) => Some(*memory.get(a as usize).unwrap_or(&0)),
+            None => Some(*memory.get(ip + i + 1).unwrap_or(&0)),
+        }
+    };
+    let write = |i: usize, value: i64, memory: &mut Vec<i64>| -> Option<()> {
+        let a = usize::try_from(address(i, memory)?).ok()?;
+        if a >= memory.len() {
+            memory.resize(a + 1, 0);
+        }
+        memory[a] = value;
+        Some(())
+    };
+
+    let next = ip as i64 + decoded.length;
+    let result = (|| -> Option<Step> {
+        let step = |ip: i64| Step::Next { ip, relative_base, output: None };
+        Some(match decoded.op_code {
+            1 => { let v = read(0, memory)? + read(1, memory)?; write(2, v, memory)?; step(next) },
+            2 => { let v = read(0, memory)? * read(1, memory)?; write(2, v, memory)?; step(next) },
+            3 => { write(0, input, memory)?; step(next) },
+            4 => Step::Next { ip: next, relative_base, output: Some(read(0, memory)?) },
+            // Both parameters are read even when the jump is not taken
+            5 => { let (v, target) = (read(0, memory)?, read(1, memory)?); step(if v != 0 { target } else { next }) },
+            6 => { let (v, target) = (read(0, memory)?, read(1, memory)?); step(if v == 0 { target } else { next }) },
+            7 => { let v = (read(0, memory)? < read(1, memory)?) as i64; write(2, v, memory)?; step(next) },
+            8 => { let v = (read(0, memory)? == read(1, memory)?) as i64; write(2, v, memory)?; step(next) },
+            9 => Step::Next { ip: next, relative_base: relative_base + read(0, memory)?, output: None },
+            _ => Step::Halted
+        })
+    })();
+    match result {
+        Some(Step::Next { ip, .. }) if ip < 0 => Step::Faulted,
+        Some(step) => step,
+        None => Step::Faulted
+    }
+}
+
+/// Helper function to generate an instruction word with any op code and mode combination
+fn instruction_word() -> impl Strategy<Value = i64> {
+    (prop::sample::select(OP_CODES.to_vec()), 0..3i64, 0..3i64, 0..3i64)
+        .prop_map(|(op_code, c, b, a)| op_code + c * 100 + b * 1_000 + a * 10_000)
+}
+
+#[test]
+fn decoder_accepts_every_mode_combination() {
+    for op_code in OP_CODES {
+        for modes in 0..27 {
+            let word = op_code + modes % 3 * 100 + modes / 3 % 3 * 1_000 + modes / 9 * 10_000;
+            let decoded = Instruction::parse_from_slice(&[word, 0, 0, 0]).expect("every mode combination decodes");
+            assert_eq!(decoded.get_op_code(), op_code, "word {}", word);
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn decoder_matches_reference(word in prop_oneof![instruction_word(), -1_000..200_000i64], operands in prop::array::uniform3(any::<i64>())) {
+        let words = [word, operands[0], operands[1], operands[2]];
+        let expected = reference_decode(word);
+        let decoded = Instruction::parse_from_slice(&words);
+        prop_assert_eq!(decoded.is_some(), expected.is_some(), "word {}", word);
+        if let (Some(decoded), Some(expected)) = (decoded, expected) {
+            prop_assert_eq!(decoded.get_op_code(), expected.op_code);
+            prop_assert_eq!(decoded.get_instruction_pointer_offset(), expected.length);
+            let output = match expected.writes {
+                true => {
+                    let i = expected.length as usize - 2;
+                    Some(Parameter { mode: mode(expected.modes[i]), value: words[i + 1] })
+                },
+                false => None
+            };
+            prop_assert_eq!(decoded.get_output().copied(), output);
+        }
+    }
+
+    #[test]
+    fn step_matches_reference(
+        word in instruction_word(),
+        cells in prop::collection::vec(-4..MEMORY_SIZE as i64 + 4, MEMORY_SIZE),
+        ip in 5..MEMORY_SIZE - 4,
+        relative_base in -4..8i64,
+        input in any::<i32>(),
+    ) {
+        // Starts with a prelude that sets the relative base and jumps to the generated instruction
+        let mut memory = cells;
+        memory[..5].copy_from_slice(&[109, relative_base, 1106, 0, ip as i64]);
+        memory[ip] = word;
+
+        let mut machine = Intcode::new(memory.clone());
+        machine.push_input(input as i64);
+        prop_assert_eq!(machine.step(), RunState::Running);
+        prop_assert_eq!(machine.step(), RunState::Running);
+        prop_assert_eq!(machine.instruction_pointer(), ip);
+        let state = machine.step();
+
+        let mut reference = memory;
+        let expected = reference_step(&mut reference, ip, relative_base, input as i64);
+        match expected {
+            Step::Halted => prop_assert_eq!(state, RunState::Halted),
+            Step::Faulted => prop_assert_eq!(state, RunState::Faulted),
+            Step::Next { ip, relative_base, output } => {
+                prop_assert_eq!(state, RunState::Running, "{:?}", machine.error());
+                prop_assert_eq!(machine.instruction_pointer() as i64, ip);
+                prop_assert_eq!(machine.relative_base(), relative_base);
+                prop_assert_eq!(machine.pop_output(), output);
+                prop_assert_eq!(machine.memory().to_vec(), Ok(reference));
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Replays the checked in fuzzing corpus, see `fuzz/`, and the crashes it found
+//!
+//! Each replay function mirrors the fuzz target of the same name so that the corpus keeps
+//! guarding against panics without a nightly toolchain
+
+use intcode::analysis::ControlFlowGraph;
+use intcode::error::IntcodeError;
+use intcode::instructions::{Instruction, op_code::OpCode};
+use intcode::io::QueueIo;
+use intcode::machine::{Backend, ExitReason, Intcode};
+use intcode::memory::Memory;
+use intcode::registry::InstructionSet;
+use std::path::Path;
+
+/// Number of instructions after which a run is stopped
+const STEP_LIMIT: u64 = 10_000;
+
+/// Helper function to read every input of a fuzz target's corpus
+fn corpus(target: &str) -> Vec<Vec<u8>> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus").join(target);
+    let mut entries = std::fs::read_dir(&directory)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", directory.display(), e))
+        .map(|entry| entry.expect("corpus entry").path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    assert!(!entries.is_empty(), "corpus {} is empty", target);
+    entries.iter().map(|path| std::fs::read(path).expect("corpus file")).collect()
+}
+
+/// Helper function to turn fuzz input into words, two bytes or eight bytes each
+fn words(bytes: &[u8], width: usize) -> Vec<i64> {
+    bytes.chunks(width)
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            match width {
+                2 => i16::from_le_bytes([word[0], word[1]]) as i64,
+                _ => i64::from_le_bytes(word)
+            }
+        })
+        .collect()
+}
+
+/// Mirrors `fuzz/fuzz_targets/decode.rs`
+fn decode(data: &[u8]) {
+    let words = words(data, 2);
+    let standard = InstructionSet::standard();
+    for start in 0..words.len() {
+        assert_eq!(Instruction::parse_from_slice(&words[start..]), standard.decode(&words[start..]));
+    }
+    let listing = intcode::disassembler::listing(&words);
+    assert_eq!(intcode::assembler::assemble(&listing).as_ref(), Ok(&words), "{}", listing);
+    ControlFlowGraph::build(&words).to_dot();
+}
+
+/// Mirrors `fuzz/fuzz_targets/load.rs`
+fn load(data: &[u8]) {
+    if let Ok(program) = intcode::loader::read_program(data) {
+        let encoded = intcode::loader::encode_binary(&program);
+        assert_eq!(intcode::loader::decode_binary(&encoded), Ok(program));
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = intcode::assembler::assemble(text);
+    }
+}
+
+/// Mirrors `fuzz/fuzz_targets/execute.rs`
+fn execute(data: &[u8]) {
+    let (options, bytes) = match data.split_first() {
+        Some(split) => split,
+        None => return
+    };
+    let program = words(bytes, if options & 2 == 0 { 2 } else { 8 });
+    let inputs = program.iter().rev().take(4).copied().collect::<Vec<i64>>();
+    let results = [Backend::Interpreter, Backend::Threaded].map(|backend| {
+        let memory = match options & 1 {
+            0 => Memory::new(program.clone()),
+            _ => Memory::paged(&program)
+        };
+        let mut machine = Intcode::with_memory(memory).with_backend(backend);
+        let outcome = machine.run(&mut QueueIo::new(inputs.clone()), Some(STEP_LIMIT));
+        (outcome, machine.relative_base(), machine.into_memory())
+    });
+    let [interpreted, threaded] = results;
+    assert_eq!(interpreted.0, threaded.0);
+    assert_eq!(interpreted.1, threaded.1);
+    assert!(interpreted.2.diff(&threaded.2).is_empty());
+}
+
+/// Helper function to run a program on every backend and get the error it faulted with
+fn fault(memory: Memory) -> IntcodeError {
+    let errors = [Backend::Interpreter, Backend::Threaded].map(|backend| {
+        let outcome = Intcode::with_memory(memory.clone()).with_backend(backend).run(&mut QueueIo::default(), Some(STEP_LIMIT));
+        match outcome.reason {
+            ExitReason::Faulted(e) => e,
+            reason => panic!("expected a fault on {:?} but the run ended with {:?}", backend, reason)
+        }
+    });
+    let [interpreted, threaded] = errors;
+    assert_eq!(interpreted, threaded);
+    interpreted
+}
+
+#[test]
+fn decode_corpus() {
+    corpus("decode").iter().for_each(|data| decode(data));
+}
+
+#[test]
+fn load_corpus() {
+    corpus("load").iter().for_each(|data| load(data));
+}
+
+#[test]
+fn execute_corpus() {
+    corpus("execute").iter().for_each(|data| execute(data));
+}
+
+#[test]
+fn short_slices_do_not_decode() {
+    for word in [1, 2, 3, 4, 5, 6, 7, 8, 9, 1105, 21108] {
+        assert_eq!(Instruction::parse_from_slice(&[word]), None, "word {}", word);
+    }
+    assert_eq!(Instruction::parse_from_slice(&[]), None);
+}
+
+#[test]
+fn overflow_faults() {
+    assert_eq!(fault(Memory::new(vec![1101, i64::MAX, 1, 0, 99])), IntcodeError::Overflow { ip: 0, word: 1101 });
+    assert_eq!(fault(Memory::new(vec![1102, i64::MIN, -1, 0, 99])), IntcodeError::Overflow { ip: 0, word: 1102 });
+    assert_eq!(fault(Memory::new(vec![109, i64::MAX, 109, 1, 99])), IntcodeError::Overflow { ip: 2, word: 109 });
+    assert_eq!(fault(Memory::new(vec![109, i64::MAX, 204, 1, 99])), IntcodeError::Overflow { ip: 2, word: 204 });
+
+    // An output at the highest address would need the next instruction past i64::MAX
+    let mut memory = Memory::paged(&[1106, 0, i64::MAX - 1]);
+    memory.write(i64::MAX - 1, 104).unwrap();
+    assert_eq!(fault(memory), IntcodeError::AddressOutOfRange { ip: i64::MAX as usize - 1, word: 104, address: i64::MAX - 1 });
+}
+
+#[test]
+fn decode_faults() {
+    // Negative words and unknown op codes fault as unknown op codes whatever their mode digits
+    assert_eq!(fault(Memory::new(vec![-1, 0, 0, 0])), IntcodeError::UnknownOpcode { ip: 0, word: -1 });
+    assert_eq!(fault(Memory::new(vec![-1101, 0, 0, 0])), IntcodeError::UnknownOpcode { ip: 0, word: -1101 });
+    assert_eq!(fault(Memory::new(vec![30042, 0, 0, 0])), IntcodeError::UnknownOpcode { ip: 0, word: 30042 });
+    assert_eq!(fault(Memory::new(vec![100042, 0, 0, 0])), IntcodeError::UnknownOpcode { ip: 0, word: 100042 });
+
+    // Known op codes with a mode digit that is not a parameter mode
+    assert_eq!(fault(Memory::new(vec![301, 0, 0, 0])), IntcodeError::InvalidParameterMode { ip: 0, word: 301 });
+    assert_eq!(fault(Memory::new(vec![90002, 0, 0, 0])), IntcodeError::InvalidParameterMode { ip: 0, word: 90002 });
+    assert_eq!(fault(Memory::new(vec![100001, 0, 0, 0])), IntcodeError::InvalidParameterMode { ip: 0, word: 100001 });
+}